@@ -0,0 +1 @@
+ALTER TABLE transactions ADD COLUMN external_id TEXT;
@@ -0,0 +1,2 @@
+-- Amount in the base currency for transactions that came with their own exchange rate
+ALTER TABLE transactions ADD COLUMN base_amount REAL;
@@ -0,0 +1,7 @@
+ALTER TABLE transactions ADD COLUMN tags TEXT NOT NULL DEFAULT '';
+
+CREATE TABLE IF NOT EXISTS rules (
+    uid INTEGER PRIMARY KEY,
+    position INTEGER NOT NULL,
+    definition TEXT NOT NULL
+) STRICT;
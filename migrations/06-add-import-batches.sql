@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS import_batches (
+    uid INTEGER PRIMARY KEY,
+    source TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    importer TEXT NOT NULL,
+    imported_at TEXT NOT NULL,
+    row_count INTEGER NOT NULL
+) STRICT;
+
+ALTER TABLE transactions ADD COLUMN batch_id INTEGER REFERENCES import_batches(uid);
@@ -0,0 +1,14 @@
+-- Previous values of the stored transactions an import batch replaced or merged into
+CREATE TABLE IF NOT EXISTS batch_changes (
+    uid INTEGER PRIMARY KEY,
+    batch_id INTEGER NOT NULL REFERENCES import_batches(uid),
+    transaction_id INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    amount REAL NOT NULL,
+    type INTEGER NOT NULL,
+    note TEXT NOT NULL,
+    external_id TEXT,
+    base_amount REAL,
+    tags TEXT NOT NULL
+) STRICT;
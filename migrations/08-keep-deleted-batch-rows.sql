@@ -0,0 +1,3 @@
+-- Transactions deleted by a batch are kept with their own batch, so a rollback can insert them again
+ALTER TABLE batch_changes ADD COLUMN deleted INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE batch_changes ADD COLUMN transaction_batch_id INTEGER;
@@ -205,6 +205,7 @@ struct TransactionTypeScore {
     donation: IncomeAndExpenses,
     tax: IncomeAndExpenses,
     banking: IncomeAndExpenses,
+    salary: IncomeAndExpenses,
     unknown: IncomeAndExpenses,
 }
 
@@ -234,6 +235,7 @@ impl TransactionTypeScore {
             TransactionType::Donation => self.donation.push(amount),
             TransactionType::Tax => self.tax.push(amount),
             TransactionType::Banking => self.banking.push(amount),
+            TransactionType::Salary => self.salary.push(amount),
             TransactionType::Unknown => self.unknown.push(amount),
         }
     }
@@ -260,6 +262,7 @@ impl IntoIterator for TransactionTypeScore {
             (TransactionType::Donation, self.donation),
             (TransactionType::Tax, self.tax),
             (TransactionType::Banking, self.banking),
+            (TransactionType::Salary, self.salary),
             (TransactionType::Unknown, self.unknown),
         ]
         .into_iter()
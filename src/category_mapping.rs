@@ -0,0 +1,173 @@
+use crate::{
+    error::{Error, Res},
+    transaction::transaction_type::TransactionType,
+};
+use std::{collections::BTreeMap, fs::File, io::BufReader, path::Path};
+
+/// Map hierarchical category or account names (e.g. `Auto:Fuel`, `Expenses:Food:Groceries`)
+/// to transaction types
+///
+/// Categories are compared case-insensitively by prefix, the longest matching entry wins.
+/// Components are separated by `:`
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategoryMapping {
+    entries: Vec<(String, TransactionType)>,
+}
+
+impl CategoryMapping {
+    pub fn new(entries: Vec<(String, TransactionType)>) -> Self {
+        Self { entries }
+    }
+
+    /// Load a JSON object that maps category names to type identifiers or names
+    ///
+    /// ```json
+    /// { "Groceries": "E", "Auto:Fuel": "Gas" }
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> Res<Self> {
+        let file = File::open(&path)?;
+        let raw: BTreeMap<String, String> =
+            serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+                Error::Import(format!(
+                    "Could not read category mapping {}: {}",
+                    path.as_ref().display(),
+                    e
+                ))
+            })?;
+
+        let mut entries = vec![];
+        for (category, raw_type) in raw {
+            let transaction_type = TransactionType::from_str(&raw_type);
+            if transaction_type == TransactionType::Unknown && !raw_type.eq_ignore_ascii_case("U") {
+                return Err(Error::Import(format!(
+                    "Unknown type '{}' for category '{}'",
+                    raw_type, category
+                )));
+            }
+            entries.push((category, transaction_type));
+        }
+
+        Ok(Self::new(entries))
+    }
+
+    /// Find the type for `category`
+    ///
+    /// If no entry matches, each component of the category is tried as a type name
+    pub fn find(&self, category: &str) -> TransactionType {
+        if let Some(transaction_type) = self.find_entry(category) {
+            return transaction_type;
+        }
+
+        category
+            .trim()
+            .rsplit(':')
+            .map(TransactionType::from_str)
+            .find(|t| *t != TransactionType::Unknown)
+            .unwrap_or(TransactionType::Unknown)
+    }
+
+    /// Find the type of the longest entry that is a prefix of `category`
+    pub fn find_entry(&self, category: &str) -> Option<TransactionType> {
+        let category = category.trim();
+
+        self.entries
+            .iter()
+            .filter(|(prefix, _)| is_prefix(prefix, category))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, transaction_type)| *transaction_type)
+    }
+
+    /// Return the category names mapped to `transaction_type`, in the order of the mapping
+    pub fn categories_for(&self, transaction_type: TransactionType) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|(_, t)| *t == transaction_type)
+            .map(|(category, _)| category.as_str())
+            .collect()
+    }
+
+    /// Return the category name to use for `transaction_type` when exporting
+    pub fn category_for(&self, transaction_type: TransactionType) -> String {
+        self.entries
+            .iter()
+            .find(|(_, t)| *t == transaction_type)
+            .map_or_else(|| transaction_type.name().to_owned(), |(c, _)| c.clone())
+    }
+}
+
+impl Default for CategoryMapping {
+    /// Common category names of personal finance tools
+    fn default() -> Self {
+        let entries = [
+            ("Auto", TransactionType::Car),
+            ("Auto:Fuel", TransactionType::Gas),
+            ("Bank Charges", TransactionType::Banking),
+            ("Charity", TransactionType::Donation),
+            ("Clothing", TransactionType::Clothes),
+            ("Dining", TransactionType::Eat),
+            ("Entertainment", TransactionType::Fun),
+            ("Fuel", TransactionType::Gas),
+            ("Gifts", TransactionType::Fun),
+            ("Groceries", TransactionType::Eat),
+            ("Household", TransactionType::Home),
+            ("Medical", TransactionType::Health),
+            ("Personal Care", TransactionType::Body),
+            ("Rent", TransactionType::Home),
+            ("Salary", TransactionType::Salary),
+            ("Taxes", TransactionType::Tax),
+            ("Telephone", TransactionType::Telecommunication),
+            ("Utilities", TransactionType::Home),
+            ("Wages", TransactionType::Salary),
+        ];
+
+        Self::new(
+            entries
+                .into_iter()
+                .map(|(category, transaction_type)| (category.to_owned(), transaction_type))
+                .collect(),
+        )
+    }
+}
+
+/// Check if `prefix` matches `category` on a component boundary
+fn is_prefix(prefix: &str, category: &str) -> bool {
+    let prefix = prefix.trim().to_lowercase();
+    let category = category.to_lowercase();
+
+    category == prefix || category.starts_with(&format!("{}:", prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_by_longest_prefix() {
+        let mapping = CategoryMapping::default();
+
+        assert_eq!(mapping.find("Auto"), TransactionType::Car);
+        assert_eq!(mapping.find("Auto:Service"), TransactionType::Car);
+        assert_eq!(mapping.find("auto:fuel"), TransactionType::Gas);
+        assert_eq!(mapping.find("Automobile"), TransactionType::Unknown);
+    }
+
+    #[test]
+    fn find_by_type_name() {
+        let mapping = CategoryMapping::new(vec![]);
+
+        assert_eq!(mapping.find("Expenses:Food"), TransactionType::Eat);
+        assert_eq!(mapping.find("Health"), TransactionType::Health);
+        assert_eq!(mapping.find("Expenses:Misc"), TransactionType::Unknown);
+    }
+
+    #[test]
+    fn category_for_type() {
+        let mapping = CategoryMapping::default();
+
+        assert_eq!(mapping.category_for(TransactionType::Gas), "Auto:Fuel");
+        assert_eq!(
+            mapping.category_for(TransactionType::Education),
+            "Education"
+        );
+    }
+}
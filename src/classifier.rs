@@ -0,0 +1,197 @@
+use crate::transaction::{transaction_type::TransactionType, Transaction};
+use std::collections::{HashMap, HashSet};
+
+/// Likely type of a transaction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Suggestion {
+    pub transaction_type: TransactionType,
+    /// Probability between 0 and 1
+    pub confidence: f64,
+}
+
+#[derive(Default)]
+struct TypeStatistics {
+    transactions: usize,
+    features: usize,
+    feature_counts: HashMap<String, usize>,
+}
+
+/// Naive Bayes classifier over the words of the note, the currency and the order of magnitude of
+/// the amount
+///
+/// It is trained from the already categorized transactions, `TransactionType::Unknown` is ignored
+pub struct Classifier {
+    statistics: HashMap<TransactionType, TypeStatistics>,
+    vocabulary: HashSet<String>,
+    total_transactions: usize,
+}
+
+impl Classifier {
+    pub fn train(transactions: &[Transaction]) -> Self {
+        let mut statistics: HashMap<TransactionType, TypeStatistics> = HashMap::new();
+        let mut vocabulary = HashSet::new();
+        let mut total_transactions = 0;
+
+        for transaction in transactions {
+            if transaction.transaction_type == TransactionType::Unknown {
+                continue;
+            }
+
+            let type_statistics = statistics.entry(transaction.transaction_type).or_default();
+            type_statistics.transactions += 1;
+            total_transactions += 1;
+            for feature in features(transaction) {
+                type_statistics.features += 1;
+                *type_statistics
+                    .feature_counts
+                    .entry(feature.clone())
+                    .or_default() += 1;
+                vocabulary.insert(feature);
+            }
+        }
+
+        Self {
+            statistics,
+            vocabulary,
+            total_transactions,
+        }
+    }
+
+    /// Return the most likely type
+    ///
+    /// No suggestion is made if none of the words of the note has been seen before
+    pub fn suggest(&self, transaction: &Transaction) -> Option<Suggestion> {
+        let features = features(transaction);
+        let has_known_word = features
+            .iter()
+            .any(|f| f.starts_with(WORD_PREFIX) && self.vocabulary.contains(f));
+        if self.total_transactions == 0 || !has_known_word {
+            return None;
+        }
+
+        let vocabulary_size = self.vocabulary.len() as f64;
+        let scores: Vec<(TransactionType, f64)> = self
+            .statistics
+            .iter()
+            .map(|(transaction_type, statistics)| {
+                let prior = (statistics.transactions as f64 / self.total_transactions as f64).ln();
+                // Laplace smoothing, so unseen features don't rule out a type
+                let denominator = statistics.features as f64 + vocabulary_size;
+                let likelihood: f64 = features
+                    .iter()
+                    .map(|feature| {
+                        let count = statistics.feature_counts.get(feature).copied().unwrap_or(0);
+                        ((count as f64 + 1.0) / denominator).ln()
+                    })
+                    .sum();
+
+                (*transaction_type, prior + likelihood)
+            })
+            .collect();
+
+        let (best_type, best_score) = scores.iter().copied().max_by(|a, b| a.1.total_cmp(&b.1))?;
+        // Normalize the log-probabilities without leaving the log-space too early
+        let total: f64 = scores
+            .iter()
+            .map(|(_, score)| (score - best_score).exp())
+            .sum();
+
+        Some(Suggestion {
+            transaction_type: best_type,
+            confidence: 1.0 / total,
+        })
+    }
+}
+
+const WORD_PREFIX: &str = "word:";
+
+fn features(transaction: &Transaction) -> Vec<String> {
+    let note = transaction
+        .note
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+    let words: HashSet<&str> = note
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1 && !word.chars().all(|c| c.is_ascii_digit()))
+        .collect();
+
+    let value = transaction.amount.value;
+    let magnitude = value.abs().max(1.0).log10().floor() as i32;
+    let sign = if value < 0.0 { '-' } else { '+' };
+
+    words
+        .into_iter()
+        .map(|word| format!("{}{}", WORD_PREFIX, word))
+        .chain([
+            format!("amount:{}{}", sign, magnitude),
+            format!("currency:{}", transaction.amount.currency.iso),
+        ])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{currency::Currency, transaction::amount::Amount};
+    use chrono::NaiveDate;
+
+    fn transaction(value: f64, transaction_type: TransactionType, note: &str) -> Transaction {
+        Transaction::new(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            Amount::new(value, Currency::eur()),
+            None,
+            transaction_type,
+            Some(note.to_owned()),
+        )
+    }
+
+    fn history() -> Vec<Transaction> {
+        vec![
+            transaction(-45.0, TransactionType::Eat, "Migros Bern"),
+            transaction(-12.5, TransactionType::Eat, "Coop Pronto"),
+            transaction(-60.0, TransactionType::Eat, "Migros Zürich"),
+            transaction(-70.0, TransactionType::Gas, "Shell Tankstelle"),
+            transaction(-65.0, TransactionType::Gas, "BP Tankstelle"),
+            transaction(-1200.0, TransactionType::Home, "Miete Januar"),
+            transaction(-5.0, TransactionType::Unknown, "Migros"),
+        ]
+    }
+
+    #[test]
+    fn suggest_type_from_history() {
+        let classifier = Classifier::train(&history());
+
+        let suggestion = classifier
+            .suggest(&transaction(
+                -38.0,
+                TransactionType::Unknown,
+                "MIGROS Basel",
+            ))
+            .unwrap();
+        assert_eq!(suggestion.transaction_type, TransactionType::Eat);
+        assert!(suggestion.confidence > 0.5 && suggestion.confidence <= 1.0);
+
+        let suggestion = classifier
+            .suggest(&transaction(
+                -80.0,
+                TransactionType::Unknown,
+                "Tankstelle Aarau",
+            ))
+            .unwrap();
+        assert_eq!(suggestion.transaction_type, TransactionType::Gas);
+    }
+
+    #[test]
+    fn no_suggestion_for_unknown_words() {
+        let classifier = Classifier::train(&history());
+        assert_eq!(
+            classifier.suggest(&transaction(-10.0, TransactionType::Unknown, "Kino")),
+            None
+        );
+        assert_eq!(
+            Classifier::train(&[]).suggest(&transaction(-10.0, TransactionType::Unknown, "Migros")),
+            None
+        );
+    }
+}
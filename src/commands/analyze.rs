@@ -1,51 +1,83 @@
 use crate::{
-    currency::Currency,
+    currency::{amount_converter::AmountConverter, Currency},
     error::Res,
     file::normalize_file_path,
     filter::Request,
     persistence::TransactionRepository,
     printer::PrinterTrait,
-    transaction::{transaction_type::TransactionType, Transaction},
+    transaction::Transaction,
     verbosity::Verbosity,
 };
 use chrono::prelude::*;
 use std::path::PathBuf;
 
-#[allow(clippy::too_many_arguments)]
 pub async fn analyze<P: PrinterTrait>(
     printer: &mut P,
-    base_currency: Currency,
+    report_currency: Currency,
     input: &PathBuf,
-    from: Option<String>,
-    to: Option<String>,
-    search: Option<String>,
-    exclude: Option<String>,
-    transaction_type: Option<TransactionType>,
+    filter_request: Request,
     verbosity: Verbosity,
 ) -> Res<()> {
     let input_file = normalize_file_path(input)?;
     let repository = TransactionRepository::new(&input_file).await?;
-    let filter_request = Request::from_arguments(from, to, transaction_type, search, exclude)?;
 
     if verbosity >= Verbosity::Info {
         printer.print_filter_request(&filter_request);
     }
 
-    let transactions_to_print = repository.fetch_with_request(filter_request).await?;
-    printer.print_transactions(&base_currency, &transactions_to_print);
+    let transactions = repository.fetch_with_request(filter_request).await?;
+    let transactions_to_print = if report_currency != Currency::base() {
+        convert_transactions(printer, &repository, &report_currency, transactions)
+    } else {
+        transactions
+    };
+    printer.print_transactions(&report_currency, &transactions_to_print);
 
     for month in 1..13 {
-        filter_and_print_month_sum(printer, &base_currency, &transactions_to_print, month);
+        filter_and_print_month_sum(printer, &report_currency, &transactions_to_print, month);
     }
 
     printer.print_newline();
-    printer.print_sum(&base_currency, &transactions_to_print);
+    printer.print_sum(&report_currency, &transactions_to_print);
     Ok(())
 }
 
+/// Replace the base amounts of the transactions with their values in `report_currency`
+fn convert_transactions<P: PrinterTrait>(
+    printer: &mut P,
+    repository: &TransactionRepository,
+    report_currency: &Currency,
+    transactions: Vec<Transaction>,
+) -> Vec<Transaction> {
+    let converted_transactions: Vec<Transaction> = transactions
+        .into_iter()
+        .map(|transaction| {
+            AmountConverter::convert_to_currency(
+                transaction,
+                report_currency,
+                repository.exchange_rate_provider(),
+            )
+        })
+        .collect();
+
+    let number_of_unconverted = converted_transactions
+        .iter()
+        .filter(|transaction| transaction.base_amount.is_none())
+        .count();
+    if number_of_unconverted > 0 {
+        printer.print_warning(format!(
+            "⚠︎ No exchange rate to {} found for {} transaction(s). They are excluded from the sums",
+            report_currency.iso, number_of_unconverted
+        ));
+        printer.print_newline();
+    }
+
+    converted_transactions
+}
+
 fn filter_and_print_month_sum<P: PrinterTrait>(
     printer: &mut P,
-    base_currency: &Currency,
+    report_currency: &Currency,
     all_transactions: &[Transaction],
     month: u32,
 ) {
@@ -54,5 +86,5 @@ fn filter_and_print_month_sum<P: PrinterTrait>(
         .filter(|i| i.date.month() == month)
         .map(Clone::clone)
         .collect();
-    printer.print_month_sum(month.into(), base_currency, &transactions);
+    printer.print_month_sum(month.into(), report_currency, &transactions);
 }
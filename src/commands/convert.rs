@@ -0,0 +1,54 @@
+use crate::{
+    currency::{
+        amount_converter::AmountConverter, exchange_rate_provider::ExchangeRateProvider, Currency,
+    },
+    error::{Error, Res},
+    file::normalize_file_path,
+    persistence::{Database, ExchangeRateRepository},
+    printer::PrinterTrait,
+    transaction::amount::Amount,
+};
+use chrono::{Local, NaiveDate};
+use std::path::PathBuf;
+
+pub async fn convert<P: PrinterTrait>(
+    printer: &mut P,
+    amount: Amount,
+    target_currency: Currency,
+    date: Option<NaiveDate>,
+    input: Option<&PathBuf>,
+) -> Res<()> {
+    let exchange_rate_repository = match input {
+        Some(input) => ExchangeRateRepository::new(&normalize_file_path(input)?).await?,
+        None => ExchangeRateRepository::from_database(Database::in_memory().await?),
+    };
+    let exchange_rate_provider =
+        ExchangeRateProvider::new(exchange_rate_repository.fetch_all().await?);
+    let date = date.unwrap_or_else(|| Local::now().date_naive());
+
+    let conversion = AmountConverter::convert_with_rates(
+        &amount,
+        date,
+        &target_currency,
+        &exchange_rate_provider,
+    )
+    .ok_or_else(|| {
+        Error::Rate(format!(
+            "No exchange rate to convert {} to {} at {}",
+            amount.currency_ref().iso,
+            target_currency.iso,
+            date
+        ))
+    })?;
+
+    printer.println(format!("{} = {}", amount, conversion.amount));
+    printer.println(format!("Date: {}", date));
+    if conversion.exchange_rates.is_empty() {
+        printer.println("Rate: none (same currency)");
+    }
+    for exchange_rate in &conversion.exchange_rates {
+        printer.println(format!("Rate: {}", exchange_rate));
+    }
+
+    Ok(())
+}
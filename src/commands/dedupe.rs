@@ -0,0 +1,161 @@
+use crate::{
+    currency::Currency,
+    duplicate_check::{merge_transactions, ClusterAction, DuplicateCheckOptions, DuplicateChecker},
+    error::Res,
+    file::normalize_file_path,
+    filter::Request,
+    persistence::TransactionRepository,
+    printer::PrinterTrait,
+    transaction::Transaction,
+    wizard::Wizard,
+};
+use std::path::PathBuf;
+
+/// Find clusters of possible duplicates in the database and merge or delete them
+///
+/// Each merge or deletion is recorded as a batch, so `import rollback` can undo it
+pub async fn dedupe<P: PrinterTrait>(
+    printer: &mut P,
+    base_currency: Currency,
+    input: &PathBuf,
+    filter_request: Request,
+    duplicate_options: DuplicateCheckOptions,
+    report_only: bool,
+) -> Res<()> {
+    let input_file = normalize_file_path(input)?;
+    let repository = TransactionRepository::new(&input_file).await?;
+    let transactions = repository.fetch_with_request(filter_request).await?;
+    let clusters = DuplicateChecker::new(duplicate_options).find_clusters(&transactions);
+
+    if clusters.is_empty() {
+        printer.println("No possible duplicates found");
+        return Ok(());
+    }
+
+    let wizard = Wizard::new();
+    let mut merged_counter = 0;
+    let mut deleted_counter = 0;
+    for (index, cluster) in clusters.iter().enumerate() {
+        printer.print_header(format!(
+            "Cluster {}/{} ({:.0}% match)",
+            index + 1,
+            clusters.len(),
+            cluster.score * 100.0
+        ));
+        for transaction in &cluster.transactions {
+            printer.print_transaction(&base_currency, transaction);
+        }
+        if report_only {
+            continue;
+        }
+
+        match wizard.read_cluster_action()? {
+            ClusterAction::Keep => {}
+            ClusterAction::Merge => {
+                let keep = wizard.read_transaction_to_keep(&cluster.transactions)?;
+                let (merged, deleted) = merge_cluster(&cluster.transactions, keep);
+
+                let batch = repository
+                    .resolve_duplicates(Some(&merged), &deleted)
+                    .await?;
+                printer.print_subheader(format!("Merged as batch #{} into:", batch.uid));
+                printer.print_transaction(&base_currency, &merged);
+                merged_counter += 1;
+                deleted_counter += deleted.len();
+            }
+            ClusterAction::Delete => {
+                let selection = wizard.read_transactions_to_delete(&cluster.transactions)?;
+                let deleted = select(&cluster.transactions, &selection);
+                if deleted.is_empty() {
+                    continue;
+                }
+
+                let batch = repository.resolve_duplicates(None, &deleted).await?;
+                printer.println(format!(
+                    "Deleted {} transactions as batch #{}",
+                    deleted.len(),
+                    batch.uid
+                ));
+                deleted_counter += deleted.len();
+            }
+            ClusterAction::Stop => break,
+        }
+    }
+
+    if report_only {
+        printer.println(format!(
+            "{} clusters of possible duplicates",
+            clusters.len()
+        ));
+    } else {
+        printer.println(format!(
+            "{} clusters / {} merged / {} transactions deleted",
+            clusters.len(),
+            merged_counter,
+            deleted_counter
+        ));
+    }
+
+    Ok(())
+}
+
+/// Merge the other transactions of the cluster into the one at `keep`, return the merged
+/// transaction and the ones to delete
+fn merge_cluster(transactions: &[&Transaction], keep: usize) -> (Transaction, Vec<Transaction>) {
+    let deleted: Vec<Transaction> = transactions
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != keep)
+        .map(|(_, transaction)| (*transaction).clone())
+        .collect();
+    let merged = deleted
+        .iter()
+        .fold(transactions[keep].clone(), |merged, other| {
+            merge_transactions(&merged, other)
+        });
+
+    (merged, deleted)
+}
+
+fn select(transactions: &[&Transaction], selection: &[usize]) -> Vec<Transaction> {
+    selection.iter().map(|i| transactions[*i].clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::{amount::Amount, transaction_type::TransactionType};
+    use chrono::NaiveDate;
+
+    fn transaction(uid: i64, transaction_type: TransactionType, note: &str) -> Transaction {
+        let mut transaction = Transaction::new(
+            NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            Amount::new(-4.5, Currency::base()),
+            None,
+            transaction_type,
+            Some(note.to_owned()),
+        );
+        transaction.uid = Some(uid);
+        transaction
+    }
+
+    #[test]
+    fn merge_and_delete_selection() {
+        let first = transaction(1, TransactionType::Unknown, "Starbucks");
+        let second = transaction(2, TransactionType::Eat, "STARBUCKS ZURICH");
+        let third = transaction(3, TransactionType::Eat, "Starbucks");
+        let cluster = vec![&first, &second, &third];
+
+        let (merged, deleted) = merge_cluster(&cluster, 0);
+        assert_eq!(merged.uid, Some(1));
+        // The kept transaction gets the known type of the others
+        assert_eq!(merged.transaction_type, TransactionType::Eat);
+        assert_eq!(
+            deleted.iter().map(|t| t.uid).collect::<Vec<_>>(),
+            vec![Some(2), Some(3)]
+        );
+
+        let deleted = select(&cluster, &[2]);
+        assert_eq!(deleted, vec![third.clone()]);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{
+    error::Res,
+    export::{self, ExportFormat, ExportOptions},
+    file::normalize_file_path,
+    filter::Request,
+    persistence::TransactionRepository,
+    printer::PrinterTrait,
+    transaction::Transaction,
+};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+};
+
+pub async fn export<P: PrinterTrait>(
+    printer: &mut P,
+    input: &PathBuf,
+    filter_request: Request,
+    format: ExportFormat,
+    output: Option<&PathBuf>,
+    options: ExportOptions,
+) -> Res<()> {
+    let input_file = normalize_file_path(input)?;
+    let repository = TransactionRepository::new(&input_file).await?;
+    // Base amounts calculated from the current exchange rates would be stored by a new import
+    let transactions = if format.is_importable() {
+        repository.fetch_stored_with_request(filter_request).await?
+    } else {
+        repository.fetch_with_request(filter_request).await?
+    };
+
+    match output {
+        Some(output) => {
+            let output_file = normalize_file_path(output)?;
+            let mut writer = BufWriter::new(File::create(&output_file)?);
+            write(&mut writer, format, &transactions, &options)?;
+            writer.flush()?;
+
+            printer.println(format!(
+                "Exported {} transactions to {}",
+                transactions.len(),
+                output_file.display()
+            ));
+        }
+        None => {
+            let mut writer = BufWriter::new(io::stdout().lock());
+            write(&mut writer, format, &transactions, &options)?;
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write<W: Write>(
+    writer: &mut W,
+    format: ExportFormat,
+    transactions: &[Transaction],
+    options: &ExportOptions,
+) -> Res<()> {
+    match format {
+        ExportFormat::Beancount => export::journal::write_beancount(writer, transactions, options),
+        ExportFormat::Csv => export::csv::write(writer, transactions, options),
+        ExportFormat::Json => export::json::write(writer, transactions, options),
+        ExportFormat::Ledger => export::journal::write_ledger(writer, transactions, options),
+        ExportFormat::Markdown => export::markdown::write(writer, transactions, options),
+        ExportFormat::Ods => export::ods::write(writer, transactions, options),
+        ExportFormat::Qif => export::qif::write(writer, transactions, options),
+        ExportFormat::Xlsx => export::xlsx::write(writer, transactions, options),
+    }
+}
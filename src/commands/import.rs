@@ -1,49 +1,306 @@
+use super::rules::load_rules;
 use crate::{
-    currency::Currency,
-    duplicate_check::DuplicateChecker,
+    category_mapping::CategoryMapping,
+    classifier::Classifier,
+    currency::{amount_converter::AmountConverter, Currency},
+    duplicate_check::{
+        merge_transactions, replace_transaction, CandidateOrigin, DuplicateAction,
+        DuplicateCheckOptions, DuplicateChecker, DuplicatePolicy,
+    },
     error::{Error, Res},
-    file::normalize_file_path,
-    import,
-    persistence::TransactionRepository,
+    file::{content_hash, expand_paths, normalize_file_path},
+    import::{
+        self,
+        csv::CsvProfile,
+        diagnostic::Diagnostic,
+        qif::QifOptions,
+        report::{ImportReport, ReportFormat},
+        ImportFormat, RowCollector,
+    },
+    persistence::{BatchChanges, ImportBatch, ImportBatchRepository, TransactionRepository},
     printer::PrinterTrait,
+    transaction::{transaction_type::TransactionType, Transaction},
     verbosity::Verbosity,
     wizard::Wizard,
 };
-use std::path::PathBuf;
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Read, Write},
+    path::PathBuf,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Outcome of an import session
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Uids of the stored batches
+    pub batches: Vec<i64>,
+    pub replaced: usize,
+    pub merged: usize,
+    /// Possible duplicates and transactions that have already been imported
+    pub skipped: usize,
+    /// Rows that could not be imported
+    pub errors: Vec<Error>,
+}
 
+/// Import all `inputs` in one session
+///
+/// Directories and glob patterns are expanded, `-` reads from stdin and requires `format`. Once
+/// `max_errors` rows could not be imported, reading stops right away and nothing is written.
+/// Possible duplicates are searched in the database and in the files imported before, each file
+/// is stored as its own batch, but all batches are written together
+#[allow(clippy::too_many_arguments)]
 pub async fn import<P: PrinterTrait>(
     printer: &mut P,
     base_currency: Currency,
-    input: &PathBuf,
+    inputs: &[PathBuf],
     output: &PathBuf,
+    format: Option<ImportFormat>,
+    profile: Option<String>,
+    date_format: Option<String>,
+    category_mapping: CategoryMapping,
+    rules_file: Option<&PathBuf>,
+    auto_assign_threshold: f64,
+    duplicate_options: DuplicateCheckOptions,
+    on_duplicate: DuplicatePolicy,
     no_interaction: bool,
+    dry_run_report: Option<ReportFormat>,
+    max_errors: Option<usize>,
     verbosity: Verbosity,
-) -> Res<()> {
-    let input_file = normalize_file_path(input)?;
+) -> Res<ImportSummary> {
+    // A dry run is meant for scripts and reviews, so it never asks for input
+    let no_interaction = no_interaction || dry_run_report.is_some();
     let output_file = normalize_file_path(output)?;
     let repository = TransactionRepository::new(&output_file).await?;
+    let batch_repository = ImportBatchRepository::new(&output_file).await?;
+
+    let input_paths = expand_paths(inputs, |path| ImportFormat::from_path(path).is_ok())?;
+    if input_paths.is_empty() {
+        return Err(Error::Import("No files to import found".to_owned()));
+    }
+    let mut input_files = vec![];
+    let mut stdin_copy = None;
+    for path in input_paths {
+        let input_file = if path.as_os_str() == "-" {
+            let format = format.ok_or_else(|| {
+                Error::Argument("Reading from stdin requires --format".to_owned())
+            })?;
+            if stdin_copy.is_some() {
+                return Err(Error::Argument(
+                    "stdin can only be imported once".to_owned(),
+                ));
+            }
+            let copy = StdinCopy::read(format)?;
+            let input_file = InputFile {
+                source: "stdin".to_owned(),
+                path: copy.path.clone(),
+                format,
+                content_hash: content_hash(&copy.path)?,
+            };
+            stdin_copy = Some(copy);
+            input_file
+        } else {
+            let path = normalize_file_path(&path)?;
+            InputFile {
+                source: path.display().to_string(),
+                format: match format {
+                    Some(format) => format,
+                    None => ImportFormat::from_path(&path)?,
+                },
+                content_hash: content_hash(&path)?,
+                path,
+            }
+        };
+        input_files.push(input_file);
+    }
+
+    // The JSON report must stay parseable, so the warning is only shown in text output
+    if dry_run_report != Some(ReportFormat::Json) {
+        for input_file in &input_files {
+            for batch in batch_repository
+                .find_by_hash(&input_file.content_hash)
+                .await?
+            {
+                printer.print_warning(format!(
+                    "⚠︎ {} has already been imported as batch #{} on {} ({} transactions)",
+                    input_file.source,
+                    batch.uid,
+                    batch.imported_at.format("%Y-%m-%d %H:%M"),
+                    batch.row_count
+                ));
+            }
+        }
+    }
+
     let current_transactions = repository.fetch_all().await?;
+    let rule_set = load_rules(rules_file, &output_file).await?;
+    let classifier = Classifier::train(&current_transactions);
+    let duplicate_checker = DuplicateChecker::new(duplicate_options);
+    let csv_profile = match profile {
+        Some(name) => CsvProfile::load(&name, &output_file)?,
+        None => CsvProfile::default(),
+    };
+    let qif_options = QifOptions {
+        date_format,
+        currency: base_currency.clone(),
+        category_mapping: category_mapping.clone(),
+    };
 
-    let result = match input_file
-        .extension()
-        .map(|e| e.to_str().expect("Path is not UTF8"))
-    {
-        Some("json") => import::json::get_transactions(input_file, |mut transaction| {
-            if !no_interaction {
-                printer.print_header("Complete the following transaction details");
-                printer.print_transaction(&base_currency, &transaction);
+    // The report lists possible duplicates itself, so a dry run doesn't resolve them
+    let resolve_duplicates = dry_run_report.is_none();
+    let mut already_imported = vec![];
+    let mut skipped_duplicates = vec![];
+    // Stored transactions changed by a duplicate, with the index of the file it came from
+    let mut replaced: Vec<(usize, Transaction)> = vec![];
+    let mut merged: Vec<(usize, Transaction)> = vec![];
+    let mut remaining_action = None;
+    let mut duplicate_conflicts = 0;
+    // Transactions of the files imported before, and the same converted to the base currency
+    let mut session_transactions: Vec<Transaction> = vec![];
+    let mut session_comparable: Vec<Transaction> = vec![];
+    let mut changed_session_indexes: Vec<usize> = vec![];
+    let mut file_transactions = vec![];
+    let mut errors = vec![];
 
-                let possible_duplicates =
-                    DuplicateChecker::get_possible_duplicates(&transaction, &current_transactions);
-                if !possible_duplicates.is_empty() {
-                    printer.print_warning("⚠︎ Found possible duplicates:");
-                    for possible_duplicate in possible_duplicates {
-                        printer.print_transaction(&base_currency, possible_duplicate);
+    for (file_index, input_file) in input_files.iter().enumerate() {
+        // Duplicates found in earlier files are updated once the current file is parsed
+        let mut session_updates: Vec<(usize, Transaction)> = vec![];
+        let prepare_transaction = |mut transaction: Transaction| {
+            if DuplicateChecker::find_by_external_id(&transaction, &current_transactions).is_some()
+                || DuplicateChecker::find_by_external_id(&transaction, &session_transactions)
+                    .is_some()
+            {
+                already_imported.push(transaction);
+                return Ok(None);
+            }
+
+            transaction = rule_set.apply(transaction);
+
+            // Compare in the base currency, so entries in other currencies are found as well
+            let comparable = AmountConverter::convert_to_currency(
+                transaction.clone(),
+                &Currency::base(),
+                repository.exchange_rate_provider(),
+            );
+            let mut possible_duplicates = if resolve_duplicates {
+                let mut candidates =
+                    duplicate_checker.find_candidates(&comparable, &current_transactions);
+                candidates.extend(duplicate_checker.find_candidates_in(
+                    &comparable,
+                    &session_comparable,
+                    CandidateOrigin::Session,
+                ));
+                candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+                candidates
+            } else {
+                vec![]
+            };
+            // A transaction is replaced or merged into only once, otherwise the last duplicate
+            // would silently overwrite the changes of the ones before
+            possible_duplicates.retain(|candidate| match candidate.origin {
+                CandidateOrigin::Session => {
+                    !changed_session_indexes.contains(&candidate.index)
+                        && !session_updates
+                            .iter()
+                            .any(|(updated, _)| *updated == candidate.index)
+                }
+                CandidateOrigin::Stored => !replaced
+                    .iter()
+                    .chain(merged.iter())
+                    .any(|(_, updated)| updated.uid == candidate.transaction.uid),
+            });
+            if !possible_duplicates.is_empty() {
+                let (action, target) = match (remaining_action, no_interaction) {
+                    (Some(action), _) => (action, 0),
+                    (None, true) => match on_duplicate {
+                        DuplicatePolicy::Skip => (DuplicateAction::Skip, 0),
+                        DuplicatePolicy::Keep => (DuplicateAction::Keep, 0),
+                        DuplicatePolicy::Fail => {
+                            duplicate_conflicts += 1;
+                            return Err(Error::Import(format!(
+                                "Possible duplicate of the transaction from {} ({}): {} {}",
+                                possible_duplicates[0].transaction.date,
+                                possible_duplicates[0].describe(),
+                                transaction.amount,
+                                transaction.note.as_deref().unwrap_or_default()
+                            )));
+                        }
+                    },
+                    (None, false) => {
+                        printer.print_header("Possible duplicate found");
+                        printer.print_transaction(&base_currency, &transaction);
+                        printer.print_warning("⚠︎ Found possible duplicates:");
+                        for possible_duplicate in &possible_duplicates {
+                            printer
+                                .print_transaction(&base_currency, possible_duplicate.transaction);
+                            printer.println(format!("  {}", possible_duplicate.describe()));
+                        }
+
+                        let wizard = Wizard::new();
+                        let (action, apply_to_remaining) = wizard.read_duplicate_action()?;
+                        if apply_to_remaining {
+                            remaining_action = Some(action);
+                        }
+                        let target = match action {
+                            DuplicateAction::Replace | DuplicateAction::Merge
+                                if !apply_to_remaining =>
+                            {
+                                wizard.read_duplicate_target(&possible_duplicates)?
+                            }
+                            _ => 0,
+                        };
+                        (action, target)
                     }
+                };
+
+                let candidate = &possible_duplicates[target];
+                // A duplicate from an earlier file is not stored yet, so it is changed in place
+                let (session_index, existing) = match candidate.origin {
+                    CandidateOrigin::Session => (
+                        Some(candidate.index),
+                        &session_transactions[candidate.index],
+                    ),
+                    CandidateOrigin::Stored => (None, candidate.transaction),
+                };
+                let updated = match action {
+                    DuplicateAction::Skip => {
+                        skipped_duplicates.push(transaction);
+                        return Ok(None);
+                    }
+                    DuplicateAction::Keep => None,
+                    DuplicateAction::Replace => Some(replace_transaction(existing, &transaction)),
+                    DuplicateAction::Merge => Some(merge_transactions(existing, &transaction)),
+                };
+                if let Some(updated) = updated {
+                    match (session_index, action) {
+                        (Some(index), _) => session_updates.push((index, updated)),
+                        (None, DuplicateAction::Replace) => replaced.push((file_index, updated)),
+                        (None, _) => merged.push((file_index, updated)),
+                    }
+                    return Ok(None);
                 }
+            }
+
+            if transaction.transaction_type != TransactionType::Unknown {
+                return Ok(Some(transaction));
+            }
+
+            let suggestion = classifier.suggest(&transaction);
+            if no_interaction {
+                if let Some(suggestion) =
+                    suggestion.filter(|s| s.confidence >= auto_assign_threshold)
+                {
+                    transaction.transaction_type = suggestion.transaction_type;
+                }
+            } else {
+                printer.print_header("Complete the following transaction details");
+                printer.print_transaction(&base_currency, &transaction);
 
                 let selected_transaction_type =
-                    Wizard::new().read_transaction_type_or_skip(true)?;
+                    Wizard::new().read_transaction_type_or_skip(true, suggestion)?;
                 match selected_transaction_type {
                     Some(i) => transaction.transaction_type = i,
                     None => return Ok(None),
@@ -51,48 +308,389 @@ pub async fn import<P: PrinterTrait>(
             }
 
             Ok(Some(transaction))
-        })?,
-        Some("md") => import::markdown::get_transactions(input_file)?,
-        Some(e) => return Err(Error::Import(format!("No parser to import {} files", e)))?,
-        None => {
+        };
+
+        let path = &input_file.path;
+        // The limit counts the errors of all files
+        let rows = RowCollector::new(
+            prepare_transaction,
+            max_errors.map(|max_errors| max_errors.saturating_sub(errors.len())),
+        );
+        let result = match input_file.format {
+            ImportFormat::Camt => import::camt::get_transactions(path, rows)?,
+            ImportFormat::Csv => import::csv::get_transactions(path, &csv_profile, rows)?,
+            ImportFormat::Journal => {
+                import::journal::get_transactions(path, &category_mapping, rows)?
+            }
+            ImportFormat::Json => import::json::get_transactions(path, rows)?,
+            ImportFormat::Markdown => import::markdown::get_transactions(path, rows)?,
+            ImportFormat::Mt940 => import::mt940::get_transactions(path, rows)?,
+            ImportFormat::Ndjson => import::json::get_ndjson_transactions(path, rows)?,
+            ImportFormat::Ofx => import::ofx::get_transactions(path, rows)?,
+            ImportFormat::Qif => import::qif::get_transactions(path, &qif_options, rows)?,
+        };
+
+        for (index, updated) in session_updates {
+            session_comparable[index] = AmountConverter::convert_to_currency(
+                updated.clone(),
+                &Currency::base(),
+                repository.exchange_rate_provider(),
+            );
+            session_transactions[index] = updated;
+            changed_session_indexes.push(index);
+        }
+        errors.extend(result.errors.into_iter().map(|error| {
+            let mut diagnostic = Diagnostic::from(error);
+            diagnostic.span.file = Some(input_file.source.clone());
+            Error::from(diagnostic)
+        }));
+        if let Some(max_errors) = max_errors.filter(|max| errors.len() >= *max) {
+            for error in errors.iter().take(max_errors) {
+                eprintln!("{}\n", error);
+            }
             return Err(Error::Import(format!(
-                "Could not detect the extension of file path {}",
-                input_file.display()
-            )))?
+                "Import aborted after {} errors, nothing was written",
+                max_errors
+            )));
         }
-    };
+        file_transactions.push(result.transactions.len());
+        session_comparable.extend(result.transactions.iter().map(|transaction| {
+            AmountConverter::convert_to_currency(
+                transaction.clone(),
+                &Currency::base(),
+                repository.exchange_rate_provider(),
+            )
+        }));
+        session_transactions.extend(result.transactions);
+    }
+    drop(stdin_copy);
 
-    let transactions = result.transactions;
-    let errors = result.errors;
+    let transactions = session_transactions;
+
+    if let Some(report_format) = dry_run_report {
+        let report = ImportReport::new(
+            &transactions,
+            &errors,
+            &already_imported,
+            &current_transactions,
+            &duplicate_checker,
+            repository.exchange_rate_provider(),
+        );
+        report.print(printer, report_format);
+
+        return Ok(ImportSummary {
+            errors,
+            ..ImportSummary::default()
+        });
+    }
 
     for error in &errors {
-        eprintln!("Error during import: {}", error);
+        eprintln!("{}\n", error);
     }
-    let mut successful_imports_counter = 0;
-    let mut failed_imports_counter = 0;
-    for transaction in &transactions {
-        if verbosity >= Verbosity::Debug {
+    if duplicate_conflicts > 0 {
+        return Err(Error::Import(format!(
+            "Import aborted, found {} possible duplicate(s) and --on-duplicate is fail",
+            duplicate_conflicts
+        )));
+    }
+    if !skipped_duplicates.is_empty() {
+        printer.print_warning(format!(
+            "Skipped {} possible duplicate(s)",
+            skipped_duplicates.len()
+        ));
+        if verbosity >= Verbosity::Info {
+            printer.print_transactions(&base_currency, &skipped_duplicates);
+        }
+    }
+    if !already_imported.is_empty() {
+        printer.print_warning(format!(
+            "Skipped {} transaction(s) that have already been imported",
+            already_imported.len()
+        ));
+        if verbosity >= Verbosity::Info {
+            printer.print_transactions(&base_currency, &already_imported);
+        }
+    }
+    if verbosity >= Verbosity::Debug {
+        for transaction in &transactions {
             printer.println(format!("Try to import transaction: {:#?}", transaction));
         }
-        if let Err(e) = repository.add(transaction).await {
-            eprintln!("Error during import of transaction: {}", e);
-            printer.print_transaction(&base_currency, transaction);
-            printer.print_newline();
+    }
+    if transactions.is_empty() && replaced.is_empty() && merged.is_empty() {
+        printer.println(format!(
+            "No transactions to import / {} parsing errors",
+            errors.len()
+        ));
+        return Ok(ImportSummary {
+            skipped: skipped_duplicates.len() + already_imported.len(),
+            errors,
+            ..ImportSummary::default()
+        });
+    }
 
-            failed_imports_counter += 1;
-        } else {
-            successful_imports_counter += 1;
+    // Split the session into one batch per file, files that changed nothing get none
+    let file_count = input_files.len();
+    let mut offset = 0;
+    let mut batches = vec![];
+    for (file_index, (input_file, count)) in
+        input_files.into_iter().zip(file_transactions).enumerate()
+    {
+        let of_file = |updates: &[(usize, Transaction)]| -> Vec<Transaction> {
+            updates
+                .iter()
+                .filter(|(index, _)| *index == file_index)
+                .map(|(_, transaction)| transaction.clone())
+                .collect()
+        };
+        let changes = BatchChanges {
+            inserted: transactions[offset..offset + count].to_vec(),
+            replaced: of_file(&replaced),
+            merged: of_file(&merged),
+        };
+        if !changes.is_empty() {
+            let batch = ImportBatch::new(
+                input_file.source,
+                input_file.content_hash,
+                input_file.format.name().to_owned(),
+            );
+            batches.push((batch, changes));
         }
+        offset += count;
     }
+    let replaced: Vec<Transaction> = replaced.into_iter().map(|(_, t)| t).collect();
+    let merged: Vec<Transaction> = merged.into_iter().map(|(_, t)| t).collect();
+    let batches = repository
+        .add_batches(batches)
+        .await
+        .map_err(|e| Error::Import(format!("Import aborted, nothing was written: {}", e)))?;
 
     printer.print_header("Imported the following transactions:");
     printer.print_transactions(&base_currency, &transactions);
+    if !replaced.is_empty() {
+        printer.print_header("Replaced the following transactions:");
+        printer.print_transactions(&base_currency, &replaced);
+    }
+    if !merged.is_empty() {
+        printer.print_header("Merged into the following transactions:");
+        printer.print_transactions(&base_currency, &merged);
+    }
+    let batch_ids: Vec<String> = batches.iter().map(|b| format!("#{}", b.uid)).collect();
+    let batch_description = if batch_ids.is_empty() {
+        "without a new batch".to_owned()
+    } else {
+        format!("as batch {}", batch_ids.join(", "))
+    };
     printer.println(format!(
-        "{} successful imports / {} failures / {} parsing errors",
-        successful_imports_counter,
-        failed_imports_counter,
+        "{} transactions imported from {} file(s) {} / {} replaced / {} merged / {} parsing errors",
+        transactions.len(),
+        file_count,
+        batch_description,
+        replaced.len(),
+        merged.len(),
         errors.len()
     ));
 
+    Ok(ImportSummary {
+        imported: transactions.len(),
+        batches: batches.iter().map(|batch| batch.uid).collect(),
+        replaced: replaced.len(),
+        merged: merged.len(),
+        skipped: skipped_duplicates.len() + already_imported.len(),
+        errors,
+    })
+}
+
+/// A file to import, with the format it is parsed with
+struct InputFile {
+    /// Path or `stdin`, as recorded with the batch
+    source: String,
+    path: PathBuf,
+    format: ImportFormat,
+    content_hash: String,
+}
+
+/// Copy of stdin in a temporary file, because the importers read from paths
+///
+/// The file is removed when the copy is dropped
+struct StdinCopy {
+    path: PathBuf,
+}
+
+impl StdinCopy {
+    fn read(format: ImportFormat) -> Res<Self> {
+        let mut content = vec![];
+        io::stdin().read_to_end(&mut content)?;
+
+        Self::create(&content, format)
+    }
+
+    /// Write `content` to a new file that only the current user can read
+    ///
+    /// The file is created with `create_new`, so a file or link that someone else placed under
+    /// the same name in the shared temporary directory is never written to
+    fn create(content: &[u8], format: ImportFormat) -> Res<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.subsec_nanos());
+        for attempt in 0..100 {
+            let path = env::temp_dir().join(format!(
+                "budgeteer-stdin-{}-{}-{}.{}",
+                process::id(),
+                nanos,
+                attempt,
+                format.name()
+            ));
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+            match options.open(&path) {
+                Ok(mut file) => {
+                    let copy = Self { path };
+                    file.write_all(content)?;
+                    return Ok(copy);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(Error::file_io(
+            "Could not create a temporary file for the input from stdin",
+        ))
+    }
+}
+
+impl Drop for StdinCopy {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// List the recorded import batches
+pub async fn batches<P: PrinterTrait>(printer: &mut P, input: &PathBuf) -> Res<()> {
+    let input_file = normalize_file_path(input)?;
+    let batches = ImportBatchRepository::new(&input_file)
+        .await?
+        .fetch_all()
+        .await?;
+
+    if batches.is_empty() {
+        printer.println("No import batches stored");
+    }
+    for batch in batches {
+        printer.println(format!(
+            "#{:<4} {} {:<8} {:>5} rows  {}",
+            batch.uid,
+            batch.imported_at.format("%Y-%m-%d %H:%M"),
+            batch.importer,
+            batch.row_count,
+            batch.source
+        ));
+    }
+
+    Ok(())
+}
+
+/// Remove an import batch and every transaction it inserted, and restore the transactions it
+/// replaced or merged into
+pub async fn rollback<P: PrinterTrait>(printer: &mut P, batch: i64, output: &PathBuf) -> Res<()> {
+    let output_file = normalize_file_path(output)?;
+    let rollback = ImportBatchRepository::new(&output_file)
+        .await?
+        .rollback(batch)
+        .await?;
+    printer.println(format!(
+        "Rolled back batch #{}, removed {} transactions / restored {} transactions",
+        batch, rollback.removed, rollback.restored
+    ));
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{printer::Printer, transaction::amount::Amount};
+    use chrono::NaiveDate;
+
+    #[tokio::test]
+    async fn markdown_duplicates_are_detected() {
+        let directory = env::temp_dir().join("budgeteer-import-markdown-test");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let database = directory.join("budget.sqlite");
+        TransactionRepository::new(&database)
+            .await
+            .unwrap()
+            .add(&Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+                Amount::new(-12.5, Currency::base()),
+                None,
+                TransactionType::Eat,
+                Some("Migros".to_owned()),
+            ))
+            .await
+            .unwrap();
+        let input = directory.join("january.md");
+        fs::write(
+            &input,
+            "| Datum | Währung | Betrag | Typ | Kommentar |\n\
+             | ----- | ------- | ------ | --- | --------- |\n\
+             | 15.01.2024 | EUR | 12.50 | E | Migros |\n\
+             | 16.01.2024 | EUR | 30 | H | Hardware store |\n",
+        )
+        .unwrap();
+
+        let import_with = |on_duplicate: DuplicatePolicy| {
+            let (input, database) = (input.clone(), database.clone());
+            async move {
+                import(
+                    &mut Printer::new(),
+                    Currency::base(),
+                    &[input],
+                    &database,
+                    None,
+                    None,
+                    None,
+                    CategoryMapping::default(),
+                    None,
+                    1.0,
+                    DuplicateCheckOptions::default(),
+                    on_duplicate,
+                    true,
+                    None,
+                    None,
+                    Verbosity::from_int(0),
+                )
+                .await
+            }
+        };
+
+        assert!(import_with(DuplicatePolicy::Fail).await.is_err());
+        let summary = import_with(DuplicatePolicy::Skip).await.unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.skipped, 1);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn stdin_copies_are_private_files() {
+        let first = StdinCopy::create(b"[]", ImportFormat::Json).unwrap();
+        let second = StdinCopy::create(b"{}", ImportFormat::Json).unwrap();
+        assert_ne!(first.path, second.path);
+        assert_eq!(fs::read(&first.path).unwrap(), b"[]");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&first.path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let path = first.path.clone();
+        drop(first);
+        assert!(!path.exists());
+    }
+}
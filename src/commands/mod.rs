@@ -1,4 +1,10 @@
 pub mod analyze;
+pub mod convert;
+pub mod dedupe;
+pub mod export;
 pub mod import;
+pub mod recategorize;
+pub mod rules;
 pub mod show_types;
+pub mod watch;
 pub mod wizard;
@@ -0,0 +1,47 @@
+use super::rules::load_rules;
+use crate::{
+    currency::Currency, error::Res, file::normalize_file_path, filter::Request,
+    persistence::TransactionRepository, printer::PrinterTrait,
+};
+use std::path::PathBuf;
+
+/// Apply the rules to stored transactions and save the changes
+pub async fn recategorize<P: PrinterTrait>(
+    printer: &mut P,
+    base_currency: Currency,
+    input: &PathBuf,
+    filter_request: Request,
+    rules_file: Option<&PathBuf>,
+    dry_run: bool,
+) -> Res<()> {
+    let input_file = normalize_file_path(input)?;
+    let rule_set = load_rules(rules_file, &input_file).await?;
+    if rule_set.is_empty() {
+        printer.print_warning("No rules to apply");
+        return Ok(());
+    }
+
+    let repository = TransactionRepository::new(&input_file).await?;
+    let transactions = repository.fetch_with_request(filter_request).await?;
+
+    let mut changed = vec![];
+    for transaction in transactions {
+        let updated = rule_set.apply(transaction.clone());
+        if updated != transaction {
+            if !dry_run {
+                repository.update(&updated).await?;
+            }
+            changed.push(updated);
+        }
+    }
+
+    printer.print_header(if dry_run {
+        "The following transactions would change:"
+    } else {
+        "Changed the following transactions:"
+    });
+    printer.print_transactions(&base_currency, &changed);
+    printer.println(format!("{} transactions changed", changed.len()));
+
+    Ok(())
+}
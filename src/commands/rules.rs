@@ -0,0 +1,90 @@
+use crate::{
+    currency::Currency,
+    error::Res,
+    file::normalize_file_path,
+    persistence::RuleRepository,
+    printer::PrinterTrait,
+    rules::RuleSet,
+    transaction::{amount::Amount, transaction_type::TransactionType, Transaction},
+};
+use chrono::{Local, NaiveDate};
+use std::path::{Path, PathBuf};
+
+/// Load the rules from `rules_file`, or the rules stored in the database if no file is given
+pub async fn load_rules(rules_file: Option<&PathBuf>, database: &Path) -> Res<RuleSet> {
+    let rules = match rules_file {
+        Some(rules_file) => RuleSet::load(normalize_file_path(rules_file)?)?,
+        None => RuleRepository::new(database).await?.fetch_all().await?,
+    };
+
+    RuleSet::new(rules)
+}
+
+pub async fn list<P: PrinterTrait>(printer: &mut P, input: &PathBuf) -> Res<()> {
+    let input_file = normalize_file_path(input)?;
+    let rules = RuleRepository::new(&input_file).await?.fetch_all().await?;
+
+    if rules.is_empty() {
+        printer.println("No rules stored");
+    }
+    for (index, rule) in rules.iter().enumerate() {
+        printer.println(format!("{:>3}. {}", index + 1, rule));
+    }
+
+    Ok(())
+}
+
+/// Replace the stored rules with the rules of `rules_file`
+pub async fn load<P: PrinterTrait>(
+    printer: &mut P,
+    rules_file: &PathBuf,
+    output: &PathBuf,
+) -> Res<()> {
+    let rules = RuleSet::load(normalize_file_path(rules_file)?)?;
+    // Validate before anything is stored
+    RuleSet::new(rules.clone())?;
+
+    let output_file = normalize_file_path(output)?;
+    RuleRepository::new(&output_file)
+        .await?
+        .replace_all(&rules)
+        .await?;
+    printer.println(format!("Stored {} rules", rules.len()));
+
+    Ok(())
+}
+
+/// Show which rule matches the described transaction and what it changes
+#[allow(clippy::too_many_arguments)]
+pub async fn test<P: PrinterTrait>(
+    printer: &mut P,
+    base_currency: Currency,
+    input: &PathBuf,
+    rules_file: Option<&PathBuf>,
+    amount: Amount,
+    date: Option<NaiveDate>,
+    note: String,
+) -> Res<()> {
+    let input_file = normalize_file_path(input)?;
+    let rule_set = load_rules(rules_file, &input_file).await?;
+
+    let transaction = Transaction::new(
+        date.unwrap_or_else(|| Local::now().date_naive()),
+        amount,
+        None,
+        TransactionType::Unknown,
+        Some(note),
+    );
+
+    match rule_set.find_match(&transaction) {
+        Some((index, rule)) => {
+            printer.print_header(format!("Rule {} matches", index + 1));
+            printer.println(rule.to_string());
+            printer.print_newline();
+            printer.print_transaction(&base_currency, &rule_set.apply(transaction));
+        }
+        None => printer.println("No rule matches"),
+    }
+
+    Ok(())
+}
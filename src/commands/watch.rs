@@ -0,0 +1,329 @@
+use super::import::{import, ImportSummary};
+use crate::{
+    category_mapping::CategoryMapping,
+    currency::Currency,
+    duplicate_check::{DuplicateCheckOptions, DuplicatePolicy},
+    error::{Error, Res},
+    file::normalize_file_path,
+    import::{diagnostic::Diagnostic, ImportFormat},
+    printer::PrinterTrait,
+    verbosity::Verbosity,
+};
+use chrono::Local;
+use notify::{
+    event::{AccessKind, AccessMode, ModifyKind, RenameMode},
+    Event, EventKind, RecursiveMode, Watcher,
+};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tokio::sync::mpsc;
+
+const PROCESSED_DIRECTORY: &str = "processed";
+const FAILED_DIRECTORY: &str = "failed";
+
+/// Settings passed on to each import
+pub struct WatchOptions {
+    pub profile: Option<String>,
+    pub category_mapping: CategoryMapping,
+    pub rules_file: Option<PathBuf>,
+    pub auto_assign_threshold: f64,
+    pub duplicate_options: DuplicateCheckOptions,
+    pub on_duplicate: DuplicatePolicy,
+}
+
+/// Import files as they arrive in `directory`
+///
+/// Files that are already in the directory are imported first. Imported files are moved to
+/// `processed/`, files that could not be imported or only had errors to `failed/`. Each result
+/// is appended to the log file, which defaults to `watch.log` in `directory`
+pub async fn watch<P: PrinterTrait>(
+    printer: &mut P,
+    base_currency: Currency,
+    directory: &Path,
+    output: &PathBuf,
+    log: Option<&PathBuf>,
+    options: WatchOptions,
+) -> Res<()> {
+    if !directory.is_dir() {
+        return Err(Error::file_io(format!(
+            "{} is not a directory",
+            directory.display()
+        )));
+    }
+    let directory = fs::canonicalize(directory)?;
+    let output_file = normalize_file_path(output)?;
+    let log_file = match log {
+        Some(log) => normalize_file_path(log)?,
+        None => directory.join("watch.log"),
+    };
+    let mut watch_log = WatchLog { path: log_file };
+
+    // Start watching before handling the existing files, so no file is missed in between
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let _ = sender.send(event);
+    })?;
+    watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+    printer.println(format!(
+        "Watching {} for new files, press Ctrl+C to stop",
+        directory.display()
+    ));
+
+    let mut existing_files = fs::read_dir(&directory)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    existing_files.sort();
+    for path in existing_files {
+        if is_importable(&path) {
+            handle_file(
+                printer,
+                &base_currency,
+                &directory,
+                &path,
+                &output_file,
+                &options,
+                &mut watch_log,
+            )
+            .await;
+        }
+    }
+
+    while let Some(event) = receiver.recv().await {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                watch_log.write(format!("Watch error: {}", e));
+                continue;
+            }
+        };
+        // Only react once a file is complete: written and closed, or moved into the directory
+        let is_complete = matches!(
+            event.kind,
+            EventKind::Access(AccessKind::Close(AccessMode::Write))
+                | EventKind::Modify(ModifyKind::Name(RenameMode::To))
+        );
+        if !is_complete {
+            continue;
+        }
+        for path in event.paths {
+            if path.parent() == Some(directory.as_path()) && is_importable(&path) {
+                handle_file(
+                    printer,
+                    &base_currency,
+                    &directory,
+                    &path,
+                    &output_file,
+                    &options,
+                    &mut watch_log,
+                )
+                .await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Import one file and move it out of the watched directory
+///
+/// Failures are logged, so the watcher keeps running
+async fn handle_file<P: PrinterTrait>(
+    printer: &mut P,
+    base_currency: &Currency,
+    directory: &Path,
+    path: &Path,
+    output_file: &PathBuf,
+    options: &WatchOptions,
+    watch_log: &mut WatchLog,
+) {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let result = import(
+        printer,
+        base_currency.clone(),
+        &[path.to_path_buf()],
+        output_file,
+        None,
+        options.profile.clone(),
+        None,
+        options.category_mapping.clone(),
+        options.rules_file.as_ref(),
+        options.auto_assign_threshold,
+        options.duplicate_options.clone(),
+        options.on_duplicate,
+        true,
+        None,
+        None,
+        Verbosity::from_int(0),
+    )
+    .await;
+
+    match &result {
+        Ok(summary) => {
+            watch_log.write(format!("{}: {}", file_name, describe(summary)));
+            for error in &summary.errors {
+                let diagnostic = Diagnostic::from(error.clone());
+                watch_log.write(format!("{}: {}", diagnostic.span, diagnostic.message));
+            }
+        }
+        Err(e) => {
+            printer.print_warning(format!("Could not import {}: {}", file_name, e));
+            watch_log.write(format!("{}: failed: {}", file_name, e));
+        }
+    }
+
+    match move_to(path, &directory.join(target_directory(&result))) {
+        Ok(target) => watch_log.write(format!("{}: moved to {}", file_name, target.display())),
+        Err(e) => {
+            printer.print_warning(format!("Could not move {}: {}", file_name, e));
+            watch_log.write(format!("{}: could not be moved: {}", file_name, e));
+        }
+    }
+}
+
+/// A file counts as failed if the import failed, or if it only produced errors
+fn target_directory(result: &Res<ImportSummary>) -> &'static str {
+    match result {
+        Ok(summary) if summary.imported > 0 || summary.errors.is_empty() => PROCESSED_DIRECTORY,
+        _ => FAILED_DIRECTORY,
+    }
+}
+
+fn describe(summary: &ImportSummary) -> String {
+    let batches: Vec<String> = summary
+        .batches
+        .iter()
+        .map(|uid| format!("#{}", uid))
+        .collect();
+
+    format!(
+        "imported {} transactions{} / {} replaced / {} merged / {} skipped / {} errors",
+        summary.imported,
+        if batches.is_empty() {
+            String::new()
+        } else {
+            format!(" as batch {}", batches.join(", "))
+        },
+        summary.replaced,
+        summary.merged,
+        summary.skipped,
+        summary.errors.len()
+    )
+}
+
+/// Whether `path` is a complete file that one of the importers can read
+fn is_importable(path: &Path) -> bool {
+    let is_hidden = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+    path.is_file() && !is_hidden && ImportFormat::from_path(path).is_ok()
+}
+
+/// Move `path` into `target_directory`, the name gets a time prefix if it is already taken
+fn move_to(path: &Path, target_directory: &Path) -> Res<PathBuf> {
+    fs::create_dir_all(target_directory)?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::file_io(format!("{} is not a file", path.display())))?
+        .to_string_lossy()
+        .to_string();
+    let mut target = target_directory.join(&file_name);
+    if target.exists() {
+        target = target_directory.join(format!(
+            "{}-{}",
+            Local::now().format("%Y%m%d-%H%M%S%.3f"),
+            file_name
+        ));
+    }
+    fs::rename(path, &target)?;
+
+    Ok(target)
+}
+
+struct WatchLog {
+    path: PathBuf,
+}
+
+impl WatchLog {
+    /// Append `message` with the current time
+    ///
+    /// If the log can't be written, the message goes to stderr instead
+    fn write<S: AsRef<str>>(&mut self, message: S) {
+        let line = format!(
+            "{} {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            message.as_ref()
+        );
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = result {
+            eprintln!(
+                "Could not write to {}: {}\n{}",
+                self.path.display(),
+                e,
+                line
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_to() {
+        let directory = std::env::temp_dir().join("budgeteer-watch-move-test");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let target_directory = directory.join(PROCESSED_DIRECTORY);
+
+        fs::write(directory.join("a.json"), "[]").unwrap();
+        assert!(is_importable(&directory.join("a.json")));
+        assert_eq!(
+            move_to(&directory.join("a.json"), &target_directory).unwrap(),
+            target_directory.join("a.json")
+        );
+
+        // A second file with the same name doesn't overwrite the first
+        fs::write(directory.join("a.json"), "[]").unwrap();
+        let target = move_to(&directory.join("a.json"), &target_directory).unwrap();
+        assert_ne!(target, target_directory.join("a.json"));
+        assert_eq!(fs::read_dir(&target_directory).unwrap().count(), 2);
+
+        fs::write(directory.join("watch.log"), "").unwrap();
+        assert!(!is_importable(&directory.join("watch.log")));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn only_errors_count_as_failed() {
+        let summary = |imported: usize, errors: usize| {
+            Ok(ImportSummary {
+                imported,
+                errors: vec![Error::Parse("Invalid amount".to_owned()); errors],
+                ..ImportSummary::default()
+            })
+        };
+
+        assert_eq!(target_directory(&summary(2, 0)), PROCESSED_DIRECTORY);
+        assert_eq!(target_directory(&summary(2, 1)), PROCESSED_DIRECTORY);
+        // Already imported files are processed as well
+        assert_eq!(target_directory(&summary(0, 0)), PROCESSED_DIRECTORY);
+        assert_eq!(target_directory(&summary(0, 1)), FAILED_DIRECTORY);
+        assert_eq!(
+            target_directory(&Err(Error::Import("Invalid file".to_owned()))),
+            FAILED_DIRECTORY
+        );
+    }
+}
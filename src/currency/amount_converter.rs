@@ -1,11 +1,227 @@
-use super::{exchange_rate::ExchangeRate, Currency};
+use super::{exchange_rate::ExchangeRate, exchange_rate_provider::ExchangeRateProvider, Currency};
 use crate::transaction::{amount::Amount, Transaction};
+use chrono::NaiveDate;
 
 pub struct AmountConverter {}
 
+/// Result of a conversion including the exchange rates that were applied
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conversion {
+    pub amount: Amount,
+    pub exchange_rates: Vec<ExchangeRate>,
+}
+
 impl AmountConverter {
     pub fn convert_to_base(transaction: Transaction, exchange_rate: ExchangeRate) -> Transaction {
         let converted_amount = transaction.amount.value() * (exchange_rate.rate);
         transaction.with_base_amount(Amount::new(converted_amount, Currency::base()))
     }
+
+    /// Store the transaction's amount converted into `currency` as its base amount
+    ///
+    /// A base amount that came with the transaction (e.g. from an imported price annotation) is
+    /// preferred over the stored exchange rates. If no exchange rate is known for the
+    /// transaction's date, the base amount is removed
+    pub fn convert_to_currency(
+        transaction: Transaction,
+        currency: &Currency,
+        exchange_rate_provider: &ExchangeRateProvider,
+    ) -> Transaction {
+        let source = match transaction.base_amount.as_ref() {
+            Some(base_amount)
+                if base_amount.currency == Currency::base()
+                    && &transaction.amount.currency != currency =>
+            {
+                base_amount
+            }
+            _ => &transaction.amount,
+        };
+
+        match Self::convert(source, transaction.date, currency, exchange_rate_provider) {
+            Some(converted_amount) => transaction.with_base_amount(converted_amount),
+            None => Transaction {
+                base_amount: None,
+                ..transaction
+            },
+        }
+    }
+
+    /// Convert `amount` into `currency` using the exchange rates valid at `date`
+    pub fn convert(
+        amount: &Amount,
+        date: NaiveDate,
+        currency: &Currency,
+        exchange_rate_provider: &ExchangeRateProvider,
+    ) -> Option<Amount> {
+        Self::convert_with_rates(amount, date, currency, exchange_rate_provider)
+            .map(|conversion| conversion.amount)
+    }
+
+    /// Convert `amount` into `currency` and collect the exchange rates that were used
+    ///
+    /// Exchange rates are stored relative to the base currency, so a conversion between two
+    /// foreign currencies goes through the base currency and uses two rates
+    pub fn convert_with_rates(
+        amount: &Amount,
+        date: NaiveDate,
+        currency: &Currency,
+        exchange_rate_provider: &ExchangeRateProvider,
+    ) -> Option<Conversion> {
+        let mut exchange_rates = vec![];
+        if amount.currency_ref() == currency {
+            return Some(Conversion {
+                amount: amount.clone(),
+                exchange_rates,
+            });
+        }
+
+        let base_currency = Currency::base();
+        let base_value = if amount.currency_ref() == &base_currency {
+            amount.value()
+        } else {
+            let exchange_rate =
+                exchange_rate_provider.find_exchange_rate_for_date(amount.currency_ref(), date)?;
+            let base_value = amount.value() * exchange_rate.rate;
+            exchange_rates.push(exchange_rate);
+            base_value
+        };
+
+        let value = if currency == &base_currency {
+            base_value
+        } else {
+            let exchange_rate =
+                exchange_rate_provider.find_exchange_rate_for_date(currency, date)?;
+            let value = base_value / exchange_rate.rate;
+            exchange_rates.push(exchange_rate);
+            value
+        };
+
+        Some(Conversion {
+            amount: Amount::new(value, currency.clone()),
+            exchange_rates,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::transaction_type::TransactionType;
+
+    fn yearly_rate(year: i32, currency: Currency, rate: f64) -> ExchangeRate {
+        ExchangeRate {
+            uid: 1,
+            year,
+            month: -1,
+            day: -1,
+            base_currency: Currency::base(),
+            currency,
+            rate,
+        }
+    }
+
+    fn provider() -> ExchangeRateProvider {
+        ExchangeRateProvider::new(vec![
+            yearly_rate(2024, Currency::chf(), 1.05),
+            yearly_rate(2024, Currency::usd(), 0.9),
+        ])
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+    }
+
+    #[test]
+    fn convert_same_currency() {
+        let amount = Amount::new(-12.5, Currency::chf());
+        assert_eq!(
+            AmountConverter::convert(&amount, date(), &Currency::chf(), &provider()),
+            Some(amount)
+        );
+    }
+
+    #[test]
+    fn convert_from_and_to_base() {
+        let converted = AmountConverter::convert(
+            &Amount::new(-100.0, Currency::chf()),
+            date(),
+            &Currency::eur(),
+            &provider(),
+        )
+        .unwrap();
+        assert_eq!(converted.currency, Currency::eur());
+        assert!((converted.value + 105.0).abs() < 1e-9);
+
+        let converted = AmountConverter::convert(
+            &Amount::new(-105.0, Currency::eur()),
+            date(),
+            &Currency::chf(),
+            &provider(),
+        )
+        .unwrap();
+        assert_eq!(converted.currency, Currency::chf());
+        assert!((converted.value + 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn convert_between_foreign_currencies() {
+        let converted = AmountConverter::convert(
+            &Amount::new(-90.0, Currency::usd()),
+            date(),
+            &Currency::chf(),
+            &provider(),
+        )
+        .unwrap();
+        assert_eq!(converted.currency, Currency::chf());
+        assert!((converted.value + 77.142857).abs() < 1e-6);
+    }
+
+    #[test]
+    fn convert_with_rates() {
+        let conversion = AmountConverter::convert_with_rates(
+            &Amount::new(-90.0, Currency::usd()),
+            date(),
+            &Currency::chf(),
+            &provider(),
+        )
+        .unwrap();
+        let currencies: Vec<Currency> = conversion
+            .exchange_rates
+            .into_iter()
+            .map(|exchange_rate| exchange_rate.currency)
+            .collect();
+        assert_eq!(currencies, vec![Currency::usd(), Currency::chf()]);
+    }
+
+    #[test]
+    fn convert_to_currency_prefers_own_base_amount() {
+        let transaction = Transaction::new(
+            date(),
+            Amount::new(-10.0, Currency::chf()),
+            Some(Amount::new(-11.0, Currency::eur())),
+            TransactionType::Eat,
+            None,
+        );
+
+        let converted =
+            AmountConverter::convert_to_currency(transaction, &Currency::eur(), &provider());
+        assert_eq!(
+            converted.base_amount,
+            Some(Amount::new(-11.0, Currency::eur()))
+        );
+    }
+
+    #[test]
+    fn convert_without_rate() {
+        let other_year = NaiveDate::from_ymd_opt(2019, 3, 1).unwrap();
+        assert_eq!(
+            AmountConverter::convert(
+                &Amount::new(-10.0, Currency::eur()),
+                other_year,
+                &Currency::chf(),
+                &provider(),
+            ),
+            None
+        );
+    }
 }
@@ -1,7 +1,9 @@
 use super::Currency;
+use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, sqlx::FromRow)]
 pub struct ExchangeRate {
+    pub uid: i64,
     pub year: i32,
     pub month: i64,
     pub day: i64,
@@ -10,6 +12,33 @@ pub struct ExchangeRate {
     pub rate: f64,
 }
 
+impl ExchangeRate {
+    /// Describe the period this rate is defined for (`-1` acts as a wildcard)
+    pub fn validity(&self) -> String {
+        if self.month == -1 {
+            format!("yearly rate for {}", self.year)
+        } else if self.day == -1 {
+            format!("monthly rate for {}-{:02}", self.year, self.month)
+        } else {
+            format!("daily rate for {}-{:02}-{:02}", self.year, self.month, self.day)
+        }
+    }
+}
+
+impl fmt::Display for ExchangeRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "1 {} = {} {} ({}, exchange rate #{})",
+            self.currency.iso,
+            self.rate,
+            self.base_currency.iso,
+            self.validity(),
+            self.uid
+        )
+    }
+}
+
 // impl FromRow<'_, SqliteRow> for ExchangeRate {
 //     fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
 //         let currency = Currency::from_str(row.try_get("currency")?).map_err(|e| {
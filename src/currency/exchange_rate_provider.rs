@@ -1,7 +1,7 @@
 use crate::transaction::Transaction;
-use chrono::Datelike;
+use chrono::{Datelike, NaiveDate};
 
-use super::exchange_rate::ExchangeRate;
+use super::{exchange_rate::ExchangeRate, Currency};
 
 pub struct ExchangeRateProvider {
     exchange_rates: Vec<ExchangeRate>,
@@ -13,8 +13,19 @@ impl ExchangeRateProvider {
     }
 
     pub fn find_exchange_rate(&self, transaction: &Transaction) -> Option<ExchangeRate> {
-        let date = transaction.date;
-        let currency = &transaction.amount.currency.iso;
+        self.find_exchange_rate_for_date(&transaction.amount.currency, transaction.date)
+    }
+
+    /// Find the rate of `currency` valid at `date`
+    ///
+    /// A rate for the exact day is preferred over a monthly rate, which is preferred over a
+    /// yearly rate
+    pub fn find_exchange_rate_for_date(
+        &self,
+        currency: &Currency,
+        date: NaiveDate,
+    ) -> Option<ExchangeRate> {
+        let currency = &currency.iso;
         let exchange_rate = self.find_by_date_configuration(
             currency,
             date.year(),
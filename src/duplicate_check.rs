@@ -1,39 +1,476 @@
-use chrono::Days;
+use crate::transaction::{
+    main_transaction_data::MainTransactionData, transaction_type::TransactionType, Transaction,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
-use crate::transaction::{main_transaction_data::MainTransactionData, Transaction};
+const AMOUNT_WEIGHT: f64 = 0.45;
+const DATE_WEIGHT: f64 = 0.35;
+/// Notes of manual entries and bank statements often differ, so they count the least
+const NOTE_WEIGHT: f64 = 0.2;
+/// Notes below this similarity describe different purchases, e.g. two coffees in different shops
+const DIFFERENT_NOTE_SIMILARITY: f64 = 0.3;
 
-pub struct DuplicateChecker {}
+/// Settings of the duplicate matcher
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateCheckOptions {
+    /// Maximum number of days between two duplicates
+    pub window_days: u64,
+    /// Relative difference allowed between the base amounts (0.02 = 2%)
+    pub amount_tolerance: f64,
+    /// Minimum score (0 to 1) of a possible duplicate
+    pub threshold: f64,
+}
+
+impl Default for DuplicateCheckOptions {
+    fn default() -> Self {
+        Self {
+            window_days: 3,
+            amount_tolerance: 0.02,
+            threshold: 0.78,
+        }
+    }
+}
+
+/// Why two transactions are considered to be the same
+#[derive(Clone, Debug, PartialEq)]
+pub enum MatchReason {
+    SameAmount,
+    /// Relative difference of the base amounts
+    SimilarBaseAmount(f64),
+    SameDate,
+    DaysApart(i64),
+    /// Similarity of the notes between 0 and 1
+    SimilarNote(f64),
+}
+
+impl fmt::Display for MatchReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchReason::SameAmount => write!(f, "same amount"),
+            MatchReason::SimilarBaseAmount(difference) => {
+                write!(f, "base amounts differ by {:.1}%", difference * 100.0)
+            }
+            MatchReason::SameDate => write!(f, "same date"),
+            MatchReason::DaysApart(1) => write!(f, "1 day apart"),
+            MatchReason::DaysApart(days) => write!(f, "{} days apart", days),
+            MatchReason::SimilarNote(similarity) => {
+                write!(f, "similar note ({:.0}%)", similarity * 100.0)
+            }
+        }
+    }
+}
+
+/// The list a candidate was found in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CandidateOrigin {
+    /// Stored in the database
+    Stored,
+    /// Read from an earlier file of the same import session, not stored yet
+    Session,
+}
+
+/// An existing transaction that could be a duplicate
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateCandidate<'a> {
+    pub transaction: &'a Transaction,
+    pub origin: CandidateOrigin,
+    /// Position of the transaction in the searched list
+    pub index: usize,
+    /// Between the threshold and 1
+    pub score: f64,
+    pub reasons: Vec<MatchReason>,
+}
+
+impl DuplicateCandidate<'_> {
+    /// Score and reasons in one line
+    pub fn describe(&self) -> String {
+        let reasons: Vec<String> = self.reasons.iter().map(|r| r.to_string()).collect();
+
+        format!("{:.0}% match: {}", self.score * 100.0, reasons.join(", "))
+    }
+}
+
+/// Transactions that could all describe the same payment
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateCluster<'a> {
+    /// Ordered by date
+    pub transactions: Vec<&'a Transaction>,
+    /// Best score of two members
+    pub score: f64,
+}
+
+/// Scores pairs of transactions by amount, date distance and note similarity
+///
+/// Amounts in different currencies are compared through their base amounts, so they must have
+/// been converted before
+#[derive(Clone, Debug, Default)]
+pub struct DuplicateChecker {
+    options: DuplicateCheckOptions,
+}
 
 impl DuplicateChecker {
-    pub fn get_possible_duplicates<'a, T: MainTransactionData>(
+    pub fn new(options: DuplicateCheckOptions) -> Self {
+        Self { options }
+    }
+
+    /// Return the stored transactions that could be duplicates of `transaction`, the most likely
+    /// first
+    pub fn find_candidates<'a, T: MainTransactionData>(
+        &self,
+        transaction: &T,
+        transactions: &'a [Transaction],
+    ) -> Vec<DuplicateCandidate<'a>> {
+        self.find_candidates_in(transaction, transactions, CandidateOrigin::Stored)
+    }
+
+    /// Like `find_candidates`, the candidates are marked with `origin`
+    pub fn find_candidates_in<'a, T: MainTransactionData>(
+        &self,
         transaction: &T,
         transactions: &'a [Transaction],
-    ) -> Vec<&'a Transaction> {
+        origin: CandidateOrigin,
+    ) -> Vec<DuplicateCandidate<'a>> {
+        let mut candidates: Vec<DuplicateCandidate> = transactions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let (score, reasons) = self.score(transaction, item)?;
+                Some(DuplicateCandidate {
+                    transaction: item,
+                    origin,
+                    index,
+                    score,
+                    reasons,
+                })
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.transaction.date.cmp(&b.transaction.date))
+        });
+
+        candidates
+    }
+
+    /// Group `transactions` into clusters of possible duplicates
+    ///
+    /// Two transactions are in the same cluster if they match directly or through other members.
+    /// Clusters are ordered by the date of their first transaction
+    pub fn find_clusters<'a>(&self, transactions: &'a [Transaction]) -> Vec<DuplicateCluster<'a>> {
+        let mut order: Vec<usize> = (0..transactions.len()).collect();
+        order.sort_by_key(|&i| transactions[i].date);
+
+        let mut parents: Vec<usize> = (0..transactions.len()).collect();
+        let mut best_scores = vec![0.0_f64; transactions.len()];
+        for (position, &i) in order.iter().enumerate() {
+            for &j in &order[position + 1..] {
+                let days = (transactions[j].date - transactions[i].date).num_days();
+                if days as u64 > self.options.window_days {
+                    break;
+                }
+                if let Some((score, _)) = self.score(&transactions[i], &transactions[j]) {
+                    let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                    parents[root_j] = root_i;
+                    best_scores[root_i] = best_scores[root_i].max(best_scores[root_j]).max(score);
+                }
+            }
+        }
+
+        let mut clusters: Vec<DuplicateCluster> = vec![];
+        let mut cluster_of_root = HashMap::new();
+        for &i in &order {
+            let root = find_root(&mut parents, i);
+            let index = *cluster_of_root.entry(root).or_insert_with(|| {
+                clusters.push(DuplicateCluster {
+                    transactions: vec![],
+                    score: 0.0,
+                });
+                clusters.len() - 1
+            });
+            clusters[index].transactions.push(&transactions[i]);
+            clusters[index].score = best_scores[root];
+        }
+        clusters.retain(|cluster| cluster.transactions.len() > 1);
+
+        clusters
+    }
+
+    /// Find the transaction that was imported with the same external ID
+    pub fn find_by_external_id<'a>(
+        transaction: &Transaction,
+        transactions: &'a [Transaction],
+    ) -> Option<&'a Transaction> {
+        let external_id = transaction.external_id.as_ref()?;
+
         transactions
             .iter()
-            .filter(|item| is_possible_duplicate(item, transaction))
-            .collect()
+            .find(|item| item.external_id.as_ref() == Some(external_id))
+    }
+
+    /// Score how likely `a` and `b` describe the same transaction
+    ///
+    /// `None` if the amounts or dates are too far apart, both notes are clearly different, or the
+    /// score is below the threshold
+    fn score<A: MainTransactionData, B: MainTransactionData>(
+        &self,
+        a: &A,
+        b: &B,
+    ) -> Option<(f64, Vec<MatchReason>)> {
+        let mut reasons = vec![];
+        let amount_score = self.score_amount(a, b, &mut reasons)?;
+        let date_score = self.score_date(a, b, &mut reasons)?;
+        let score = match score_note(a, b, &mut reasons) {
+            Some(note_score) if note_score < DIFFERENT_NOTE_SIMILARITY => return None,
+            Some(note_score) => {
+                AMOUNT_WEIGHT * amount_score + DATE_WEIGHT * date_score + NOTE_WEIGHT * note_score
+            }
+            // Without a note only amount and date can be compared
+            None => {
+                (AMOUNT_WEIGHT * amount_score + DATE_WEIGHT * date_score)
+                    / (AMOUNT_WEIGHT + DATE_WEIGHT)
+            }
+        };
+
+        (score >= self.options.threshold).then_some((score, reasons))
+    }
+
+    fn score_amount<A: MainTransactionData, B: MainTransactionData>(
+        &self,
+        a: &A,
+        b: &B,
+        reasons: &mut Vec<MatchReason>,
+    ) -> Option<f64> {
+        let (a_amount, b_amount) = if a.amount().currency == b.amount().currency {
+            (a.amount(), b.amount())
+        } else {
+            (a.base_amount_ref()?, b.base_amount_ref()?)
+        };
+        if a_amount.currency != b_amount.currency {
+            return None;
+        }
+
+        let (a_value, b_value) = (a_amount.value, b_amount.value);
+        let difference = (a_value - b_value).abs();
+        if difference < 0.005 {
+            reasons.push(MatchReason::SameAmount);
+            return Some(1.0);
+        }
+        if a_value.signum() != b_value.signum() {
+            return None;
+        }
+
+        let relative_difference = difference / a_value.abs().max(b_value.abs());
+        if relative_difference > self.options.amount_tolerance {
+            return None;
+        }
+        reasons.push(MatchReason::SimilarBaseAmount(relative_difference));
+
+        Some(1.0 - 0.5 * relative_difference / self.options.amount_tolerance)
+    }
+
+    fn score_date<A: MainTransactionData, B: MainTransactionData>(
+        &self,
+        a: &A,
+        b: &B,
+        reasons: &mut Vec<MatchReason>,
+    ) -> Option<f64> {
+        let days = (a.date() - b.date()).num_days().abs();
+        if days as u64 > self.options.window_days {
+            return None;
+        }
+
+        if days == 0 {
+            reasons.push(MatchReason::SameDate);
+            return Some(1.0);
+        }
+        reasons.push(MatchReason::DaysApart(days));
+
+        // The last day of the window still counts half
+        Some(1.0 - days as f64 / (2.0 * self.options.window_days as f64))
+    }
+}
+
+fn score_note<A: MainTransactionData, B: MainTransactionData>(
+    a: &A,
+    b: &B,
+    reasons: &mut Vec<MatchReason>,
+) -> Option<f64> {
+    let a_note = a.note_ref().filter(|note| !note.trim().is_empty())?;
+    let b_note = b.note_ref().filter(|note| !note.trim().is_empty())?;
+
+    let similarity = note_similarity(a_note, b_note);
+    if similarity >= 0.5 {
+        reasons.push(MatchReason::SimilarNote(similarity));
+    }
+
+    Some(similarity)
+}
+
+/// Similarity between 0 and 1, the best of word overlap and edit distance
+///
+/// A note whose words all appear in the other note (e.g. "Migros" and "MIGROS BERN 1234") counts
+/// as similar
+fn note_similarity(a: &str, b: &str) -> f64 {
+    let a = a.trim().to_lowercase();
+    let b = b.trim().to_lowercase();
+    if a == b {
+        return 1.0;
+    }
+
+    let a_tokens = tokens(&a);
+    let b_tokens = tokens(&b);
+    let common = a_tokens.intersection(&b_tokens).count();
+    let union = a_tokens.union(&b_tokens).count();
+    let overlap = if union == 0 {
+        0.0
+    } else {
+        common as f64 / union as f64
+    };
+    let contained = if common > 0 && common == a_tokens.len().min(b_tokens.len()) {
+        0.8
+    } else {
+        0.0
+    };
+
+    let length = a.chars().count().max(b.chars().count());
+    let edit_similarity = 1.0 - levenshtein(&a, &b) as f64 / length as f64;
+
+    overlap.max(contained).max(edit_similarity)
+}
+
+fn tokens(text: &str) -> HashSet<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.chars().count() > 1)
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
     }
+
+    previous[b.len()]
 }
 
-fn is_possible_duplicate<A: MainTransactionData, B: MainTransactionData>(a: &A, b: &B) -> bool {
-    if a.amount() != b.amount() {
-        return false;
+fn find_root(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
     }
-    if a.date() == b.date() {
-        return true;
+    // Shorten the path for the next lookup
+    let mut current = i;
+    while parents[current] != root {
+        let next = parents[current];
+        parents[current] = root;
+        current = next;
     }
-    let three_days_before = b
-        .date()
-        .checked_sub_days(Days::new(3))
-        .expect("Date is out of range");
 
-    let three_days_after = b
-        .date()
-        .checked_add_days(Days::new(3))
-        .expect("Date is out of range");
+    root
+}
+
+/// What to do with an incoming transaction that has possible duplicates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DuplicateAction {
+    /// Drop the incoming transaction
+    Skip,
+    /// Store both transactions
+    Keep,
+    /// Overwrite the existing transaction with the incoming one
+    Replace,
+    /// Complete the existing transaction with the note, tags and type of the incoming one
+    Merge,
+}
 
-    a.date() >= three_days_before && a.date() <= three_days_after
+/// What to do with a cluster of duplicates in the database
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClusterAction {
+    /// Leave all transactions untouched
+    Keep,
+    /// Merge all transactions into one and delete the others
+    Merge,
+    /// Delete some of the transactions
+    Delete,
+    /// Stop walking through the clusters
+    Stop,
+}
+
+/// Handling of possible duplicates without user interaction
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum DuplicatePolicy {
+    Skip,
+    Keep,
+    /// Abort the import
+    Fail,
+}
+
+/// Return `incoming` with the ID of `existing`
+///
+/// The existing type is kept if the incoming one is unknown
+pub fn replace_transaction(existing: &Transaction, incoming: &Transaction) -> Transaction {
+    let transaction_type = if incoming.transaction_type == TransactionType::Unknown {
+        existing.transaction_type
+    } else {
+        incoming.transaction_type
+    };
+
+    Transaction {
+        uid: existing.uid,
+        transaction_type,
+        ..incoming.clone()
+    }
+}
+
+/// Complete `existing` with the data of `incoming`
+///
+/// Date and amounts are kept. Different notes are joined, tags are combined and the incoming
+/// type and external ID are only used if the existing ones are missing
+pub fn merge_transactions(existing: &Transaction, incoming: &Transaction) -> Transaction {
+    let note = match (existing.note.as_deref(), incoming.note.as_deref()) {
+        (Some(a), Some(b)) if !a.trim().is_empty() && !b.trim().is_empty() => {
+            if a.to_lowercase().contains(&b.to_lowercase()) {
+                Some(a.to_owned())
+            } else {
+                Some(format!("{} / {}", a, b))
+            }
+        }
+        (Some(a), _) if !a.trim().is_empty() => Some(a.to_owned()),
+        _ => incoming.note.clone(),
+    };
+    let transaction_type = if existing.transaction_type == TransactionType::Unknown {
+        incoming.transaction_type
+    } else {
+        existing.transaction_type
+    };
+    let mut tags = existing.tags.clone();
+    for tag in &incoming.tags {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+
+    Transaction {
+        transaction_type,
+        note,
+        external_id: existing
+            .external_id
+            .clone()
+            .or_else(|| incoming.external_id.clone()),
+        tags,
+        ..existing.clone()
+    }
 }
 
 #[cfg(test)]
@@ -61,6 +498,10 @@ mod tests {
         }
     }
 
+    fn is_possible_duplicate<A: MainTransactionData, B: MainTransactionData>(a: &A, b: &B) -> bool {
+        DuplicateChecker::default().score(a, b).is_some()
+    }
+
     fn x_eur(value: f64) -> Amount {
         Amount::new(value, Currency::eur())
     }
@@ -135,4 +576,139 @@ mod tests {
             },
         ));
     }
+
+    fn transaction(day: u32, amount: Amount, base_value: f64, note: &str) -> Transaction {
+        Transaction::new(
+            NaiveDate::from_ymd_opt(2024, 5, day).unwrap(),
+            amount,
+            None,
+            TransactionType::Unknown,
+            Some(note.to_owned()),
+        )
+        .with_base_amount(x_eur(base_value))
+    }
+
+    #[test]
+    fn find_candidates_across_currencies() {
+        let existing = vec![
+            transaction(10, x_eur(-52.30), -52.30, "Migros"),
+            transaction(11, x_eur(-80.0), -80.0, "Migros"),
+        ];
+        let imported = transaction(
+            11,
+            Amount::new(-51.0, Currency::chf()),
+            -52.0,
+            "MIGROS BERN 1234",
+        );
+
+        let candidates = DuplicateChecker::default().find_candidates(&imported, &existing);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].transaction, &existing[0]);
+        assert!(matches!(
+            candidates[0].reasons[..],
+            [
+                MatchReason::SimilarBaseAmount(_),
+                MatchReason::DaysApart(1),
+                MatchReason::SimilarNote(_)
+            ]
+        ));
+    }
+
+    #[test]
+    fn rank_candidates() {
+        let existing = vec![
+            transaction(8, x_eur(-4.50), -4.50, "Starbucks"),
+            transaction(9, x_eur(-4.50), -4.50, "STARBUCKS"),
+            transaction(9, x_eur(-4.50), -4.50, "Bakery"),
+        ];
+        let coffee = transaction(9, x_eur(-4.50), -4.50, "Starbucks");
+
+        // The same amount at a different shop on the same day is not a duplicate
+        let candidates = DuplicateChecker::default().find_candidates(&coffee, &existing);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].transaction, &existing[1]);
+        assert_eq!(candidates[0].index, 1);
+        assert_eq!(candidates[0].origin, CandidateOrigin::Stored);
+        let in_session = DuplicateChecker::default().find_candidates_in(
+            &coffee,
+            &existing,
+            CandidateOrigin::Session,
+        );
+        assert_eq!(in_session[0].origin, CandidateOrigin::Session);
+        assert_eq!(candidates[1].transaction, &existing[0]);
+        assert!(candidates[0].score > candidates[1].score);
+
+        let other_shop = transaction(10, x_eur(-4.50), -4.50, "Bakery");
+        let candidates = DuplicateChecker::default().find_candidates(&other_shop, &existing[..2]);
+        assert!(candidates.is_empty());
+
+        // Without notes only amount and date are compared
+        let without_note = transaction(9, x_eur(-4.50), -4.50, "");
+        assert_eq!(
+            DuplicateChecker::default()
+                .find_candidates(&without_note, &existing)
+                .len(),
+            3
+        );
+
+        let narrow = DuplicateChecker::new(DuplicateCheckOptions {
+            window_days: 0,
+            ..Default::default()
+        });
+        assert_eq!(narrow.find_candidates(&coffee, &existing).len(), 1);
+    }
+
+    #[test]
+    fn similarity_of_notes() {
+        assert_eq!(note_similarity("Migros", " migros "), 1.0);
+        assert_eq!(note_similarity("Migros", "MIGROS BERN 1234"), 0.8);
+        assert!(note_similarity("Shell Tankstelle", "Migros") < 0.3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn merge_and_replace() {
+        let mut existing = transaction(10, x_eur(-52.30), -52.30, "Migros");
+        existing.uid = Some(7);
+        existing.transaction_type = TransactionType::Eat;
+        existing.tags = vec!["weekly".to_owned()];
+        let mut incoming = transaction(11, x_eur(-52.0), -52.0, "MIGROS BERN 1234");
+        incoming.external_id = Some("REF-1".to_owned());
+        incoming.tags = vec!["card".to_owned()];
+
+        let merged = merge_transactions(&existing, &incoming);
+        assert_eq!(merged.uid, Some(7));
+        assert_eq!(merged.date, existing.date);
+        assert_eq!(merged.amount, existing.amount);
+        assert_eq!(merged.note.as_deref(), Some("Migros / MIGROS BERN 1234"));
+        assert_eq!(merged.tags, vec!["weekly".to_owned(), "card".to_owned()]);
+        assert_eq!(merged.external_id.as_deref(), Some("REF-1"));
+        assert_eq!(merged.transaction_type, TransactionType::Eat);
+
+        let replaced = replace_transaction(&existing, &incoming);
+        assert_eq!(replaced.uid, Some(7));
+        assert_eq!(replaced.date, incoming.date);
+        assert_eq!(replaced.note, incoming.note);
+        assert_eq!(replaced.transaction_type, TransactionType::Eat);
+    }
+
+    #[test]
+    fn find_clusters() {
+        let transactions = vec![
+            transaction(1, x_eur(-30.0), -30.0, "Migros"),
+            transaction(20, x_eur(-4.50), -4.50, "Starbucks"),
+            transaction(2, x_eur(-30.0), -30.0, "MIGROS BERN"),
+            transaction(4, x_eur(-30.0), -30.0, "Migros Bern"),
+            transaction(21, x_eur(-4.50), -4.50, "Bakery"),
+            transaction(28, x_eur(-4.50), -4.50, "Starbucks"),
+        ];
+
+        let clusters = DuplicateChecker::default().find_clusters(&transactions);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(
+            clusters[0].transactions,
+            vec![&transactions[0], &transactions[2], &transactions[3]]
+        );
+        assert!(clusters[0].score > 0.85);
+    }
 }
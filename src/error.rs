@@ -1,3 +1,4 @@
+use crate::import::diagnostic::Diagnostic;
 use std::error;
 use std::fmt;
 use std::io;
@@ -18,6 +19,7 @@ pub enum Error {
     LineSeparator,
     LineComment,
     Persistence(String),
+    Diagnostic(Box<Diagnostic>),
 }
 
 impl Error {
@@ -33,6 +35,24 @@ impl Error {
             Error::LineSeparator => "Line separator",
             Error::Rate(_) => "Rate Error",
             Error::Persistence(_) => "Persistence Error",
+            Error::Diagnostic(_) => "Import Error",
+        }
+    }
+
+    /// Return the message without the error kind prefix
+    pub fn message(&self) -> String {
+        match *self {
+            Error::Argument(ref s)
+            | Error::FileIO(ref s)
+            | Error::Parse(ref s)
+            | Error::Rate(ref s)
+            | Error::Import(ref s)
+            | Error::General(ref s)
+            | Error::Persistence(ref s) => s.clone(),
+            Error::Diagnostic(ref d) => d.message.clone(),
+            Error::LineEmpty | Error::LineSeparator | Error::LineComment => {
+                self.description().to_owned()
+            }
         }
     }
 
@@ -57,6 +77,7 @@ impl fmt::Display for Error {
             Error::General(ref s) => write!(f, "{}: {}", self.description(), s),
             Error::Rate(ref s) => write!(f, "{}: {}", self.description(), s),
             Error::Persistence(ref s) => write!(f, "{}: {}", self.description(), s),
+            Error::Diagnostic(ref d) => write!(f, "{}", d),
         }
     }
 }
@@ -109,6 +130,12 @@ impl From<dialoguer::Error> for Error {
     }
 }
 
+impl From<notify::Error> for Error {
+    fn from(e: notify::Error) -> Self {
+        Error::FileIO(format!("Could not watch directory: {}", &e))
+    }
+}
+
 impl From<sqlx::Error> for Error {
     fn from(e: sqlx::Error) -> Self {
         Error::Persistence(format!("Persistence error: {}", &e))
@@ -0,0 +1,234 @@
+use super::ExportOptions;
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    transaction::{join_tags, Transaction},
+};
+use std::io::Write;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Columns that can be exported
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum CsvColumn {
+    Id,
+    Date,
+    Amount,
+    Currency,
+    /// Amount in the base currency
+    BaseAmount,
+    /// Exchange rate from the currency into the base currency
+    Rate,
+    /// Single letter of the type
+    TypeIdentifier,
+    TypeName,
+    Note,
+    Tags,
+}
+
+impl CsvColumn {
+    /// Columns that are written if no selection is given
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            CsvColumn::Date,
+            CsvColumn::Amount,
+            CsvColumn::Currency,
+            CsvColumn::BaseAmount,
+            CsvColumn::TypeName,
+            CsvColumn::Note,
+            CsvColumn::Tags,
+        ]
+    }
+
+    fn header(&self) -> &'static str {
+        match self {
+            CsvColumn::Id => "id",
+            CsvColumn::Date => "date",
+            CsvColumn::Amount => "amount",
+            CsvColumn::Currency => "currency",
+            CsvColumn::BaseAmount => "base_amount",
+            CsvColumn::Rate => "rate",
+            CsvColumn::TypeIdentifier => "type",
+            CsvColumn::TypeName => "type_name",
+            CsvColumn::Note => "note",
+            CsvColumn::Tags => "tags",
+        }
+    }
+}
+
+/// Settings of the CSV export
+///
+/// The defaults follow RFC 4180, German Excel expects `;` as delimiter and `,` as decimal
+/// separator
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvOptions {
+    pub columns: Vec<CsvColumn>,
+    pub delimiter: char,
+    pub decimal_separator: char,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            columns: CsvColumn::defaults(),
+            delimiter: ',',
+            decimal_separator: '.',
+        }
+    }
+}
+
+/// Write the transactions as CSV with a header row
+pub fn write<W: Write>(
+    writer: &mut W,
+    transactions: &[Transaction],
+    options: &ExportOptions,
+) -> Res<()> {
+    let csv_options = &options.csv;
+    if !csv_options.delimiter.is_ascii() {
+        return Err(Error::Argument(format!(
+            "The CSV delimiter '{}' must be an ASCII character",
+            csv_options.delimiter
+        )));
+    }
+    if csv_options.delimiter == csv_options.decimal_separator {
+        return Err(Error::Argument(
+            "The CSV delimiter and the decimal separator must differ".to_owned(),
+        ));
+    }
+    let date_format = options
+        .date_format
+        .as_deref()
+        .unwrap_or(DEFAULT_DATE_FORMAT);
+
+    let mut csv_writer = csv::WriterBuilder::new()
+        .delimiter(csv_options.delimiter as u8)
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(writer);
+    let csv_error = |e: csv::Error| Error::FileIO(format!("Could not write CSV: {}", e));
+    csv_writer
+        .write_record(csv_options.columns.iter().map(CsvColumn::header))
+        .map_err(csv_error)?;
+    for transaction in transactions {
+        let record = csv_options.columns.iter().map(|column| {
+            field(
+                transaction,
+                *column,
+                date_format,
+                csv_options.decimal_separator,
+            )
+        });
+        csv_writer.write_record(record).map_err(csv_error)?;
+    }
+    csv_writer.flush()?;
+
+    Ok(())
+}
+
+fn field(
+    transaction: &Transaction,
+    column: CsvColumn,
+    date_format: &str,
+    decimal_separator: char,
+) -> String {
+    let number = |value: f64, precision: usize| {
+        format!("{:.*}", precision, value).replace('.', &decimal_separator.to_string())
+    };
+    let base_amount = match &transaction.base_amount {
+        Some(base_amount) => Some(base_amount.value),
+        None if transaction.amount.currency == Currency::base() => Some(transaction.amount.value),
+        None => None,
+    };
+
+    match column {
+        CsvColumn::Id => transaction
+            .uid
+            .map(|uid| uid.to_string())
+            .unwrap_or_default(),
+        CsvColumn::Date => transaction.date.format(date_format).to_string(),
+        CsvColumn::Amount => number(transaction.amount.value, 2),
+        CsvColumn::Currency => transaction.amount.currency.iso.clone(),
+        CsvColumn::BaseAmount => base_amount
+            .map(|value| number(value, 2))
+            .unwrap_or_default(),
+        CsvColumn::Rate => base_amount
+            .filter(|_| transaction.amount.value != 0.0)
+            .map(|value| number(value / transaction.amount.value, 6))
+            .unwrap_or_default(),
+        CsvColumn::TypeIdentifier => transaction.transaction_type.identifier().to_string(),
+        CsvColumn::TypeName => transaction.transaction_type.name().to_owned(),
+        CsvColumn::Note => transaction.note.clone().unwrap_or_default(),
+        CsvColumn::Tags => join_tags(&transaction.tags),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::{amount::Amount, transaction_type::TransactionType};
+    use chrono::NaiveDate;
+
+    fn transactions() -> Vec<Transaction> {
+        let mut transaction = Transaction::new(
+            NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+            Amount::new(-50.0, Currency::usd()),
+            Some(Amount::new(-45.0, Currency::base())),
+            TransactionType::Eat,
+            Some("Dinner; \"Luigi's\"".to_owned()),
+        );
+        transaction.uid = Some(7);
+        transaction.tags = vec!["trip".to_owned(), "rome".to_owned()];
+
+        vec![transaction]
+    }
+
+    fn export(options: ExportOptions) -> String {
+        let mut output = vec![];
+        write(&mut output, &transactions(), &options).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn write_rfc_4180() {
+        let options = ExportOptions {
+            csv: CsvOptions {
+                columns: vec![
+                    CsvColumn::Id,
+                    CsvColumn::Date,
+                    CsvColumn::Amount,
+                    CsvColumn::Currency,
+                    CsvColumn::Rate,
+                    CsvColumn::TypeIdentifier,
+                    CsvColumn::Note,
+                    CsvColumn::Tags,
+                ],
+                ..CsvOptions::default()
+            },
+            ..ExportOptions::default()
+        };
+
+        assert_eq!(
+            export(options),
+            "id,date,amount,currency,rate,type,note,tags\r\n\
+             7,2024-05-02,-50.00,USD,0.900000,E,\"Dinner; \"\"Luigi's\"\"\",\"trip,rome\"\r\n"
+        );
+    }
+
+    #[test]
+    fn write_for_german_excel() {
+        let options = ExportOptions {
+            date_format: Some("%d.%m.%Y".to_owned()),
+            csv: CsvOptions {
+                columns: vec![CsvColumn::Date, CsvColumn::BaseAmount, CsvColumn::Note],
+                delimiter: ';',
+                decimal_separator: ',',
+            },
+            ..ExportOptions::default()
+        };
+
+        assert_eq!(
+            export(options),
+            "date;base_amount;note\r\n02.05.2024;-45,00;\"Dinner; \"\"Luigi's\"\"\"\r\n"
+        );
+    }
+}
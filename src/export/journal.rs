@@ -0,0 +1,333 @@
+use super::ExportOptions;
+use crate::{currency::Currency, error::Res, transaction::Transaction};
+use chrono::NaiveDate;
+use std::{collections::BTreeMap, io::Write};
+
+const DEFAULT_LEDGER_DATE_FORMAT: &str = "%Y/%m/%d";
+const DEFAULT_FUNDING_ACCOUNT: &str = "Assets:Checking";
+/// Top-level account names shared by ledger, hledger and beancount
+const ROOT_ACCOUNTS: [&str; 5] = ["Assets", "Liabilities", "Equity", "Income", "Expenses"];
+
+/// Write the transactions as a ledger journal (also readable by hledger)
+pub fn write_ledger<W: Write>(
+    writer: &mut W,
+    transactions: &[Transaction],
+    options: &ExportOptions,
+) -> Res<()> {
+    let date_format = options
+        .date_format
+        .as_deref()
+        .unwrap_or(DEFAULT_LEDGER_DATE_FORMAT);
+
+    for transaction in transactions {
+        writeln!(
+            writer,
+            "{} {}",
+            transaction.date.format(date_format),
+            single_line(transaction.note.as_deref().unwrap_or_default())
+        )?;
+        write_postings(writer, transaction, options, false)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+/// Write the transactions as a beancount file including the `open` directives of all accounts
+///
+/// Beancount only accepts ISO dates, so the configured date format is ignored
+pub fn write_beancount<W: Write>(
+    writer: &mut W,
+    transactions: &[Transaction],
+    options: &ExportOptions,
+) -> Res<()> {
+    writeln!(
+        writer,
+        "option \"operating_currency\" \"{}\"",
+        Currency::base().iso
+    )?;
+    writeln!(writer)?;
+
+    // Every account must be opened before its first use
+    let mut first_use: BTreeMap<String, NaiveDate> = BTreeMap::new();
+    for transaction in transactions {
+        for account in [
+            funding_account(options, true),
+            type_account(transaction, options, true),
+        ] {
+            let date = first_use.entry(account).or_insert(transaction.date);
+            *date = (*date).min(transaction.date);
+        }
+    }
+    for (account, date) in &first_use {
+        writeln!(writer, "{} open {}", date.format("%Y-%m-%d"), account)?;
+    }
+    if !first_use.is_empty() {
+        writeln!(writer)?;
+    }
+
+    for transaction in transactions {
+        let narration = single_line(transaction.note.as_deref().unwrap_or_default())
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"");
+        writeln!(
+            writer,
+            "{} * \"{}\"",
+            transaction.date.format("%Y-%m-%d"),
+            narration
+        )?;
+        write_postings(writer, transaction, options, true)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+/// Write the posting to the type's account, the funding account's amount is left out so it is
+/// balanced by the journal tool
+fn write_postings<W: Write>(
+    writer: &mut W,
+    transaction: &Transaction,
+    options: &ExportOptions,
+    beancount: bool,
+) -> Res<()> {
+    let amount = &transaction.amount;
+    let price = match transaction.base_amount.as_ref() {
+        Some(base_amount) if amount.currency != base_amount.currency && amount.value != 0.0 => {
+            format!(
+                " @ {} {}",
+                format_rate(base_amount.value / amount.value),
+                base_amount.currency.iso
+            )
+        }
+        _ => String::new(),
+    };
+
+    // Expenses are stored as negative amounts, but increase the expense account
+    writeln!(
+        writer,
+        "    {}  {:.2} {}{}",
+        type_account(transaction, options, beancount),
+        -amount.value,
+        amount.currency.iso,
+        price
+    )?;
+    writeln!(writer, "    {}", funding_account(options, beancount))?;
+
+    Ok(())
+}
+
+fn funding_account(options: &ExportOptions, beancount: bool) -> String {
+    let account = options
+        .funding_account
+        .as_deref()
+        .unwrap_or(DEFAULT_FUNDING_ACCOUNT);
+
+    format_account(account, beancount)
+}
+
+/// Return the account for the type of `transaction`
+///
+/// Expenses are booked below `Expenses` and positive amounts below `Income`. A category of the
+/// mapping that is an account below the matching root is used as it is, so income can be
+/// mapped separately (e.g. `"Income:Refunds": "E"`). Other categories are placed below the
+/// matching root, unless they belong to an account like `Assets`
+fn type_account(transaction: &Transaction, options: &ExportOptions, beancount: bool) -> String {
+    let root = if transaction.amount.value > 0.0 {
+        "Income"
+    } else {
+        "Expenses"
+    };
+    let mapping = &options.category_mapping;
+    let transaction_type = transaction.transaction_type;
+    if let Some(account) = mapping
+        .categories_for(transaction_type)
+        .into_iter()
+        .find(|category| category.split(':').next() == Some(root))
+    {
+        return format_account(account, beancount);
+    }
+
+    let category = mapping.category_for(transaction_type);
+    let first = category.split(':').next().unwrap_or_default();
+    let account = if first == "Income" || first == "Expenses" {
+        format!("{}{}", root, &category[first.len()..])
+    } else if ROOT_ACCOUNTS.contains(&first) {
+        category.clone()
+    } else {
+        format!("{}:{}", root, category)
+    };
+
+    format_account(&account, beancount)
+}
+
+/// Beancount account components must start with a capital letter or digit and may not contain
+/// spaces
+fn format_account(account: &str, beancount: bool) -> String {
+    if !beancount {
+        return account.to_owned();
+    }
+
+    account
+        .split(':')
+        .map(|component| {
+            let mut component: String = component
+                .trim()
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '-' })
+                .collect();
+            if let Some(first) = component.get(..1) {
+                component.replace_range(..1, &first.to_uppercase());
+            }
+            component
+        })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Format an exchange rate without insignificant trailing digits
+fn format_rate(rate: f64) -> String {
+    let formatted = format!("{:.8}", rate);
+
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_owned()
+}
+
+fn single_line(text: &str) -> String {
+    text.replace("<br />", " ").replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        category_mapping::CategoryMapping,
+        transaction::{amount::Amount, transaction_type::TransactionType},
+    };
+
+    fn transactions() -> Vec<Transaction> {
+        vec![
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+                Amount::new(-66.6, Currency::eur()),
+                Some(Amount::new(-66.6, Currency::eur())),
+                TransactionType::Gas,
+                Some("Gas station".to_owned()),
+            ),
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+                Amount::new(-10.0, Currency::chf()),
+                Some(Amount::new(-10.5, Currency::eur())),
+                TransactionType::Eat,
+                Some("Lunch \"Zum Löwen\"".to_owned()),
+            ),
+        ]
+    }
+
+    #[test]
+    fn write_ledger_journal() {
+        let options = ExportOptions {
+            funding_account: Some("Assets:Bank:Giro".to_owned()),
+            ..Default::default()
+        };
+
+        let mut output = vec![];
+        write_ledger(&mut output, &transactions(), &options).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "2024/05/02 Gas station
+    Expenses:Auto:Fuel  66.60 EUR
+    Assets:Bank:Giro
+
+2024/05/01 Lunch \"Zum Löwen\"
+    Expenses:Dining  10.00 CHF @ 1.05 EUR
+    Assets:Bank:Giro
+
+"
+        );
+    }
+
+    #[test]
+    fn write_beancount_file() {
+        let mut output = vec![];
+        write_beancount(&mut output, &transactions(), &ExportOptions::default()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "option \"operating_currency\" \"EUR\"
+
+2024-05-01 open Assets:Checking
+2024-05-02 open Expenses:Auto:Fuel
+2024-05-01 open Expenses:Dining
+
+2024-05-02 * \"Gas station\"
+    Expenses:Auto:Fuel  66.60 EUR
+    Assets:Checking
+
+2024-05-01 * \"Lunch \\\"Zum Löwen\\\"\"
+    Expenses:Dining  10.00 CHF @ 1.05 EUR
+    Assets:Checking
+
+"
+        );
+    }
+
+    #[test]
+    fn income_accounts() {
+        let income = |transaction_type: TransactionType| {
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 5, 31).unwrap(),
+                Amount::new(2500.0, Currency::eur()),
+                None,
+                transaction_type,
+                None,
+            )
+        };
+        let account = |transaction: &Transaction, options: &ExportOptions| {
+            type_account(transaction, options, false)
+        };
+
+        let options = ExportOptions::default();
+        assert_eq!(
+            account(&income(TransactionType::Salary), &options),
+            "Income:Salary"
+        );
+        assert_eq!(
+            account(&income(TransactionType::Eat), &options),
+            "Income:Dining"
+        );
+
+        let options = ExportOptions {
+            category_mapping: CategoryMapping::new(vec![
+                ("Expenses:Food".to_owned(), TransactionType::Eat),
+                ("Income:Refunds".to_owned(), TransactionType::Eat),
+                ("Expenses:Bank".to_owned(), TransactionType::Banking),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            account(&income(TransactionType::Eat), &options),
+            "Income:Refunds"
+        );
+        assert_eq!(account(&transactions()[1], &options), "Expenses:Food");
+        assert_eq!(
+            account(&income(TransactionType::Banking), &options),
+            "Income:Bank"
+        );
+    }
+
+    #[test]
+    fn format_beancount_account() {
+        assert_eq!(
+            format_account("Expenses:Bank Charges:fees", true),
+            "Expenses:Bank-Charges:Fees"
+        );
+        assert_eq!(
+            format_account("Expenses:Bank Charges", false),
+            "Expenses:Bank Charges"
+        );
+    }
+}
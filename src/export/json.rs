@@ -0,0 +1,119 @@
+use super::ExportOptions;
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    import::json::SCHEMA_VERSION,
+    transaction::Transaction,
+};
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u64,
+    transactions: Vec<TransactionJson<'a>>,
+}
+
+/// A transaction in the schema read by `import::json`
+#[derive(Serialize)]
+struct TransactionJson<'a> {
+    date: String,
+    amount: f64,
+    currency: &'a str,
+    #[serde(rename = "type")]
+    transaction_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_amount: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    external_id: Option<&'a str>,
+}
+
+/// Write the transactions as a versioned JSON document, which `import` reads back unchanged
+///
+/// The base amount is only written for foreign currencies, for the base currency it equals
+/// the amount
+pub fn write<W: Write>(
+    writer: &mut W,
+    transactions: &[Transaction],
+    _options: &ExportOptions,
+) -> Res<()> {
+    let document = Document {
+        version: SCHEMA_VERSION,
+        transactions: transactions
+            .iter()
+            .map(|transaction| TransactionJson {
+                date: transaction.date.format("%Y-%m-%d").to_string(),
+                amount: transaction.amount.value,
+                currency: &transaction.amount.currency.iso,
+                transaction_type: transaction.transaction_type.identifier().to_string(),
+                // Missing notes are stored as empty text
+                note: transaction.note.as_deref().filter(|note| !note.is_empty()),
+                tags: &transaction.tags,
+                base_amount: transaction
+                    .base_amount
+                    .as_ref()
+                    .filter(|_| transaction.amount.currency != Currency::base())
+                    .map(|base_amount| base_amount.value),
+                external_id: transaction.external_id.as_deref(),
+            })
+            .collect(),
+    };
+
+    serde_json::to_writer_pretty(&mut *writer, &document)
+        .map_err(|e| Error::FileIO(format!("Could not write JSON: {}", e)))?;
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        import::{json, RowCollector},
+        transaction::{amount::Amount, transaction_type::TransactionType},
+    };
+    use chrono::NaiveDate;
+
+    #[test]
+    fn write_and_read_back() {
+        let mut foreign = Transaction::new(
+            NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+            Amount::new(-50.25, Currency::usd()),
+            Some(Amount::new(-45.1, Currency::base())),
+            TransactionType::Eat,
+            Some("Dinner \"Luigi's\"".to_owned()),
+        );
+        foreign.tags = vec!["trip".to_owned(), "account:Card".to_owned()];
+        foreign.external_id = Some("TX-1".to_owned());
+        let transactions = vec![
+            foreign,
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 5, 3).unwrap(),
+                Amount::new(2500.0, Currency::base()),
+                None,
+                TransactionType::Unknown,
+                None,
+            ),
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 5, 4).unwrap(),
+                Amount::new(-12.0, Currency::usd()),
+                None,
+                TransactionType::Eat,
+                Some("Lunch".to_owned()),
+            ),
+        ];
+
+        let mut output = vec![];
+        write(&mut output, &transactions, &ExportOptions::default()).unwrap();
+        let document = serde_json::from_slice(&output).unwrap();
+
+        let result = json::parse(document, RowCollector::new(|t| Ok(Some(t)), None)).unwrap();
+        assert!(result.errors.is_empty());
+        assert_eq!(result.transactions, transactions);
+    }
+}
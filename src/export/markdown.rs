@@ -0,0 +1,136 @@
+use super::ExportOptions;
+use crate::{
+    currency::Currency,
+    error::Res,
+    transaction::{join_tags, Transaction},
+};
+use std::io::Write;
+
+const HEADER: [&str; 8] = [
+    "Datum",
+    "Währung",
+    "Betrag",
+    "Typ",
+    "Kommentar",
+    "Tags",
+    "Basisbetrag",
+    "Referenz",
+];
+
+/// Write the transactions as a Markdown pipe table, which `import` reads back unchanged
+///
+/// Like in hand-written files, expenses are written without sign and income with a leading `+`
+pub fn write<W: Write>(
+    writer: &mut W,
+    transactions: &[Transaction],
+    _options: &ExportOptions,
+) -> Res<()> {
+    write_row(writer, HEADER.iter().map(|name| name.to_string()))?;
+    write_row(
+        writer,
+        HEADER.iter().map(|name| "-".repeat(name.chars().count())),
+    )?;
+
+    for transaction in transactions {
+        let base_amount = transaction
+            .base_amount
+            .as_ref()
+            .filter(|_| transaction.amount.currency != Currency::base())
+            .map(|base_amount| base_amount.value.abs().to_string());
+
+        write_row(
+            writer,
+            [
+                transaction.date.format("%d.%m.%Y").to_string(),
+                transaction.amount.currency.iso.clone(),
+                signed_amount(transaction.amount.value),
+                transaction.transaction_type.identifier().to_string(),
+                escape(transaction.note.as_deref().unwrap_or_default()),
+                escape(&join_tags(&transaction.tags)),
+                base_amount.unwrap_or_default(),
+                escape(transaction.external_id.as_deref().unwrap_or_default()),
+            ]
+            .into_iter(),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_row<W: Write, I: Iterator<Item = String>>(writer: &mut W, cells: I) -> Res<()> {
+    let cells: Vec<String> = cells.collect();
+    writeln!(writer, "| {} |", cells.join(" | "))?;
+
+    Ok(())
+}
+
+fn signed_amount(value: f64) -> String {
+    if value < 0.0 {
+        value.abs().to_string()
+    } else {
+        format!("+{}", value)
+    }
+}
+
+/// Keep the text in one cell, `import` turns `<br />` back into a line break
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace("\r\n", "<br />")
+        .replace(['\r', '\n'], "<br />")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        import::{
+            markdown::{FileReader, TransactionParser},
+            RowCollector,
+        },
+        transaction::{amount::Amount, transaction_type::TransactionType},
+    };
+    use chrono::NaiveDate;
+
+    #[test]
+    fn write_and_read_back() {
+        let mut foreign = Transaction::new(
+            NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+            Amount::new(-50.25, Currency::usd()),
+            Some(Amount::new(-45.1, Currency::base())),
+            TransactionType::Eat,
+            Some("Rock | Pop".to_owned()),
+        );
+        foreign.tags = vec!["trip".to_owned(), "account:Card".to_owned()];
+        foreign.external_id = Some("TX-1".to_owned());
+        let transactions = vec![
+            foreign,
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 5, 3).unwrap(),
+                Amount::new(2500.0, Currency::base()),
+                None,
+                TransactionType::Unknown,
+                None,
+            ),
+            // Without a stored base amount, the exchange rate of the import is used later
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 5, 4).unwrap(),
+                Amount::new(-12.0, Currency::usd()),
+                None,
+                TransactionType::Eat,
+                Some("Lunch\nwith team".to_owned()),
+            ),
+        ];
+
+        let mut output = vec![];
+        write(&mut output, &transactions, &ExportOptions::default()).unwrap();
+        let content = String::from_utf8(output).unwrap();
+        assert!(content.contains("| 02.05.2024 | USD | 50.25 | E | Rock \\| Pop |"));
+        assert!(content.contains("| Lunch<br />with team |  |  |  |"));
+
+        let read = FileReader::read_tables(content.lines().map(|l| Ok(l.to_owned())));
+        let result = TransactionParser::new()
+            .parse_tables(read.tables, RowCollector::new(|t| Ok(Some(t)), None));
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.transactions, transactions);
+    }
+}
@@ -0,0 +1,46 @@
+use self::csv::CsvOptions;
+use crate::category_mapping::CategoryMapping;
+
+pub mod csv;
+pub mod journal;
+pub mod json;
+pub mod markdown;
+pub mod ods;
+pub mod qif;
+pub mod spreadsheet;
+pub mod xlsx;
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Beancount,
+    Csv,
+    /// Versioned JSON document as read by `import`
+    Json,
+    /// Ledger journal, also read by hledger
+    Ledger,
+    /// Markdown pipe table as read by `import`
+    Markdown,
+    /// OpenDocument spreadsheet with transactions, types per month and currencies
+    Ods,
+    Qif,
+    /// Excel workbook with transactions, types per month and currencies
+    Xlsx,
+}
+
+impl ExportFormat {
+    /// Whether `import` reads the format back, then only stored base amounts are written
+    pub fn is_importable(&self) -> bool {
+        matches!(self, Self::Json | Self::Markdown)
+    }
+}
+
+/// Settings shared by the export formats
+#[derive(Clone, Debug, Default)]
+pub struct ExportOptions {
+    /// Date format in `chrono` syntax (each format has its own default)
+    pub date_format: Option<String>,
+    pub category_mapping: CategoryMapping,
+    /// Account that pays for the transactions in journal formats
+    pub funding_account: Option<String>,
+    pub csv: CsvOptions,
+}
@@ -0,0 +1,200 @@
+use super::{
+    spreadsheet::{self, Cell, Sheet},
+    ExportOptions,
+};
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    transaction::Transaction,
+};
+use std::io::{Cursor, Write};
+use zip::{result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+const MIMETYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+
+const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+ <manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.spreadsheet"/>
+ <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+const CONTENT_START: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:number="urn:oasis:names:tc:opendocument:xmlns:datastyle:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" office:version="1.2">
+<office:automatic-styles>
+<number:date-style style:name="N-date"><number:year number:style="long"/><number:text>-</number:text><number:month number:style="long"/><number:text>-</number:text><number:day number:style="long"/></number:date-style>
+<style:style style:name="ce-date" style:family="table-cell" style:data-style-name="N-date"/>
+<style:style style:name="ce-header" style:family="table-cell"><style:text-properties fo:font-weight="bold"/></style:style>
+"#;
+
+/// Write the report workbook in the OpenDocument format
+pub fn write<W: Write>(
+    writer: &mut W,
+    transactions: &[Transaction],
+    _options: &ExportOptions,
+) -> Res<()> {
+    let content = content(&spreadsheet::sheets(transactions));
+    let buffer =
+        package(&content).map_err(|e| Error::FileIO(format!("Could not write ODS: {}", e)))?;
+    writer.write_all(&buffer)?;
+
+    Ok(())
+}
+
+/// Zip the document, the `mimetype` entry has to come first and stay uncompressed
+fn package(content: &str) -> Result<Vec<u8>, ZipError> {
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+    zip.start_file(
+        "mimetype",
+        SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    zip.write_all(MIMETYPE.as_bytes())?;
+    zip.start_file("META-INF/manifest.xml", SimpleFileOptions::default())?;
+    zip.write_all(MANIFEST.as_bytes())?;
+    zip.start_file("content.xml", SimpleFileOptions::default())?;
+    zip.write_all(content.as_bytes())?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+fn content(sheets: &[Sheet]) -> String {
+    let mut currencies: Vec<Currency> = vec![];
+    for currency in sheets.iter().flat_map(Sheet::currencies) {
+        if !currencies.contains(&currency) {
+            currencies.push(currency);
+        }
+    }
+
+    let mut content = CONTENT_START.to_owned();
+    for (index, currency) in currencies.iter().enumerate() {
+        content.push_str(&currency_style(index, currency));
+    }
+    content.push_str("</office:automatic-styles>\n<office:body>\n<office:spreadsheet>\n");
+
+    for sheet in sheets {
+        content.push_str(&format!(
+            "<table:table table:name=\"{}\">\n",
+            escape(sheet.name)
+        ));
+        for (row_index, row) in sheet.rows.iter().enumerate() {
+            content.push_str("<table:table-row>");
+            for cell in row {
+                content.push_str(&table_cell(cell, row_index == 0, &currencies));
+            }
+            content.push_str("</table:table-row>\n");
+        }
+        content.push_str("</table:table>\n");
+    }
+    content.push_str("</office:spreadsheet>\n</office:body>\n</office:document-content>\n");
+
+    content
+}
+
+/// Two decimals with the currency symbol, negative values in red
+fn currency_style(index: usize, currency: &Currency) -> String {
+    let number = "<number:number number:decimal-places=\"2\" number:min-decimal-places=\"2\" \
+                  number:min-integer-digits=\"1\" number:grouping=\"true\"/>";
+    let symbol = format!(
+        "<number:text> </number:text><number:currency-symbol>{}</number:currency-symbol>",
+        escape(&currency.symbol)
+    );
+
+    format!(
+        "<number:currency-style style:name=\"N-cur{index}P0\" style:volatile=\"true\">{number}{symbol}</number:currency-style>\n\
+         <number:currency-style style:name=\"N-cur{index}\"><style:text-properties fo:color=\"#ff0000\"/>\
+         <number:text>-</number:text>{number}{symbol}\
+         <style:map style:condition=\"value()&gt;=0\" style:apply-style-name=\"N-cur{index}P0\"/></number:currency-style>\n\
+         <style:style style:name=\"ce-cur{index}\" style:family=\"table-cell\" style:data-style-name=\"N-cur{index}\"/>\n"
+    )
+}
+
+fn table_cell(cell: &Cell, header: bool, currencies: &[Currency]) -> String {
+    match cell {
+        Cell::Empty => "<table:table-cell/>".to_owned(),
+        Cell::Text(text) => format!(
+            "<table:table-cell{} office:value-type=\"string\"><text:p>{}</text:p></table:table-cell>",
+            if header {
+                " table:style-name=\"ce-header\""
+            } else {
+                ""
+            },
+            escape(text)
+        ),
+        Cell::Date(date) => format!(
+            "<table:table-cell table:style-name=\"ce-date\" office:value-type=\"date\" \
+             office:date-value=\"{date}\"><text:p>{date}</text:p></table:table-cell>",
+            date = date.format("%Y-%m-%d")
+        ),
+        Cell::Number(value) => format!(
+            "<table:table-cell office:value-type=\"float\" office:value=\"{value}\">\
+             <text:p>{value}</text:p></table:table-cell>"
+        ),
+        Cell::Money(value, currency) => format!(
+            "<table:table-cell table:style-name=\"ce-cur{}\" office:value-type=\"currency\" \
+             office:currency=\"{}\" office:value=\"{}\"><text:p>{:.2} {}</text:p></table:table-cell>",
+            currencies
+                .iter()
+                .position(|c| c == currency)
+                .unwrap_or_default(),
+            escape(&currency.iso),
+            value,
+            value,
+            escape(&currency.symbol)
+        ),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn write_currency_cells() {
+        let mut output = vec![];
+        write(
+            &mut output,
+            &spreadsheet::tests::transactions(),
+            &ExportOptions::default(),
+        )
+        .unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(output)).unwrap();
+        assert_eq!(archive.by_index(0).unwrap().name(), "mimetype");
+        let mut content = String::new();
+        archive
+            .by_name("content.xml")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+
+        let document = roxmltree::Document::parse(&content).unwrap();
+        let cell = document
+            .descendants()
+            .find(|node| {
+                node.attribute((
+                    "urn:oasis:names:tc:opendocument:xmlns:office:1.0",
+                    "currency",
+                )) == Some("USD")
+            })
+            .unwrap();
+        assert_eq!(
+            cell.attribute(("urn:oasis:names:tc:opendocument:xmlns:office:1.0", "value")),
+            Some("-20")
+        );
+        assert_eq!(
+            cell.attribute((
+                "urn:oasis:names:tc:opendocument:xmlns:office:1.0",
+                "value-type"
+            )),
+            Some("currency")
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use super::ExportOptions;
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    transaction::Transaction,
+};
+use std::io::Write;
+
+const DEFAULT_DATE_FORMAT: &str = "%m/%d/%Y";
+
+/// Write the transactions as a QIF `Bank` section
+///
+/// QIF has no notion of currencies, therefore amounts are written in the base currency. A
+/// foreign transaction without base amount is an error, as it can't be written correctly
+pub fn write<W: Write>(
+    writer: &mut W,
+    transactions: &[Transaction],
+    options: &ExportOptions,
+) -> Res<()> {
+    let date_format = options
+        .date_format
+        .as_deref()
+        .unwrap_or(DEFAULT_DATE_FORMAT);
+
+    writeln!(writer, "!Type:Bank")?;
+    for transaction in transactions {
+        let amount = match transaction.base_amount.as_ref() {
+            Some(base_amount) => base_amount,
+            None if transaction.amount.currency == Currency::base() => &transaction.amount,
+            None => {
+                return Err(Error::Rate(format!(
+                "No exchange rate to convert {} from {} into {}, QIF can only hold one currency",
+                transaction.amount,
+                transaction.date,
+                Currency::base().iso
+            )))
+            }
+        };
+
+        writeln!(writer, "D{}", transaction.date.format(date_format))?;
+        writeln!(writer, "T{:.2}", amount.value)?;
+        if let Some(note) = transaction.note.as_deref().filter(|n| !n.is_empty()) {
+            writeln!(writer, "P{}", single_line(note))?;
+        }
+        writeln!(
+            writer,
+            "L{}",
+            options
+                .category_mapping
+                .category_for(transaction.transaction_type)
+        )?;
+        writeln!(writer, "^")?;
+    }
+
+    Ok(())
+}
+
+fn single_line(text: &str) -> String {
+    text.replace("<br />", " ").replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        import::{
+            qif::{self, QifOptions},
+            RowCollector,
+        },
+        transaction::{amount::Amount, transaction_type::TransactionType},
+    };
+    use chrono::NaiveDate;
+
+    #[test]
+    fn write_and_read_back() {
+        let transactions = vec![
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+                Amount::new(-66.6, Currency::eur()),
+                None,
+                TransactionType::Gas,
+                Some("Gas station".to_owned()),
+            ),
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, 5, 3).unwrap(),
+                Amount::new(-12.0, Currency::eur()),
+                None,
+                TransactionType::Health,
+                None,
+            ),
+        ];
+
+        let mut output = vec![];
+        write(&mut output, &transactions, &ExportOptions::default()).unwrap();
+        let content = String::from_utf8(output).unwrap();
+        assert!(
+            content.starts_with("!Type:Bank\nD05/02/2024\nT-66.60\nPGas station\nLAuto:Fuel\n^\n")
+        );
+
+        let result = qif::parse(
+            &content,
+            &QifOptions::default(),
+            RowCollector::new(|t| Ok(Some(t)), None),
+        );
+        assert!(result.errors.is_empty());
+        assert_eq!(result.transactions, transactions);
+    }
+
+    #[test]
+    fn write_foreign_transactions() {
+        let mut foreign = Transaction::new(
+            NaiveDate::from_ymd_opt(2024, 5, 4).unwrap(),
+            Amount::new(-20.0, Currency::usd()),
+            Some(Amount::new(-18.0, Currency::base())),
+            TransactionType::Eat,
+            Some("Lunch".to_owned()),
+        );
+
+        let mut output = vec![];
+        write(&mut output, &[foreign.clone()], &ExportOptions::default()).unwrap();
+        let content = String::from_utf8(output).unwrap();
+        assert_eq!(
+            content,
+            "!Type:Bank\nD05/04/2024\nT-18.00\nPLunch\nLDining\n^\n"
+        );
+
+        // The note is read back unchanged
+        let result = qif::parse(
+            &content,
+            &QifOptions::default(),
+            RowCollector::new(|t| Ok(Some(t)), None),
+        );
+        assert_eq!(result.transactions[0].note, foreign.note);
+
+        foreign.base_amount = None;
+        assert!(write(&mut vec![], &[foreign], &ExportOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{
+    calculator::Calculator,
+    currency::Currency,
+    month::Month,
+    transaction::{join_tags, transaction_type::TransactionType, Transaction},
+};
+use chrono::{Datelike, NaiveDate};
+
+/// Value of a cell, numbers are kept as numbers so the spreadsheet can calculate with them
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cell {
+    Empty,
+    Text(String),
+    Date(NaiveDate),
+    Number(f64),
+    Money(f64, Currency),
+}
+
+/// A sheet whose first row is the header
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sheet {
+    pub name: &'static str,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Sheet {
+    /// Currencies used by the money cells, in the order they first appear
+    pub fn currencies(&self) -> Vec<Currency> {
+        let mut currencies: Vec<Currency> = vec![];
+        for cell in self.rows.iter().flatten() {
+            if let Cell::Money(_, currency) = cell {
+                if !currencies.contains(currency) {
+                    currencies.push(currency.clone());
+                }
+            }
+        }
+
+        currencies
+    }
+}
+
+/// Build the sheets of the spreadsheet export
+///
+/// The sums are calculated in the base currency like in `analyze`, transactions without base
+/// amount are left out of them
+pub fn sheets(transactions: &[Transaction]) -> Vec<Sheet> {
+    vec![
+        transactions_sheet(transactions),
+        types_per_month_sheet(transactions),
+        currencies_sheet(transactions),
+    ]
+}
+
+fn header(names: &[&str]) -> Vec<Cell> {
+    names
+        .iter()
+        .map(|name| Cell::Text(name.to_string()))
+        .collect()
+}
+
+fn transactions_sheet(transactions: &[Transaction]) -> Sheet {
+    let mut rows = vec![header(&[
+        "Date",
+        "Type",
+        "Note",
+        "Amount",
+        "Currency",
+        "Base amount",
+        "Tags",
+    ])];
+    for transaction in transactions {
+        rows.push(vec![
+            Cell::Date(transaction.date),
+            Cell::Text(transaction.transaction_type.to_string()),
+            Cell::Text(transaction.note.clone().unwrap_or_default()),
+            Cell::Money(
+                transaction.amount.value,
+                transaction.amount.currency.clone(),
+            ),
+            Cell::Text(transaction.amount.currency.iso.clone()),
+            transaction
+                .base_amount
+                .as_ref()
+                .map_or(Cell::Empty, |base_amount| {
+                    Cell::Money(base_amount.value, Currency::base())
+                }),
+            Cell::Text(join_tags(&transaction.tags)),
+        ]);
+    }
+
+    Sheet {
+        name: "Transactions",
+        rows,
+    }
+}
+
+/// Expenses per type and month, with the expenses and the balance of each month below
+///
+/// Like `analyze`, the same month of different years is summed up
+fn types_per_month_sheet(transactions: &[Transaction]) -> Sheet {
+    let months: Vec<Vec<Transaction>> = (1..13)
+        .map(|month| {
+            transactions
+                .iter()
+                .filter(|transaction| transaction.date.month() == month)
+                .cloned()
+                .collect()
+        })
+        .collect();
+    let money = |value: f64| Cell::Money(value, Currency::base());
+
+    let mut header_row = vec![Cell::Text("Type".to_owned())];
+    header_row.extend((1..13u32).map(|month| Cell::Text(Month::from(month).to_string())));
+    header_row.push(Cell::Text("Total".to_owned()));
+
+    let mut rows = vec![header_row];
+    for transaction_type in TransactionType::all() {
+        let mut row = vec![Cell::Text(transaction_type.to_string())];
+        row.extend(months.iter().map(|month_transactions| {
+            money(Calculator::totals_for_type(month_transactions, transaction_type).expenses)
+        }));
+        row.push(money(
+            Calculator::totals_for_type(transactions, transaction_type).expenses,
+        ));
+        rows.push(row);
+    }
+
+    let mut expenses_row = vec![Cell::Text("Expenses".to_owned())];
+    expenses_row.extend(
+        months
+            .iter()
+            .map(|month_transactions| money(Calculator::totals(month_transactions).expenses)),
+    );
+    expenses_row.push(money(Calculator::totals(transactions).expenses));
+    rows.push(expenses_row);
+
+    let mut balance_row = vec![Cell::Text("Balance".to_owned())];
+    balance_row.extend(
+        months
+            .iter()
+            .map(|month_transactions| money(Calculator::sum(month_transactions))),
+    );
+    balance_row.push(money(Calculator::sum(transactions)));
+    rows.push(balance_row);
+
+    Sheet {
+        name: "Types per month",
+        rows,
+    }
+}
+
+fn currencies_sheet(transactions: &[Transaction]) -> Sheet {
+    let mut currencies: Vec<Currency> = vec![];
+    for transaction in transactions {
+        if !currencies.contains(&transaction.amount.currency) {
+            currencies.push(transaction.amount.currency.clone());
+        }
+    }
+    currencies.sort_by(|a, b| a.iso.cmp(&b.iso));
+
+    let mut rows = vec![header(&[
+        "Currency",
+        "Transactions",
+        "Income",
+        "Expenses",
+        "Total",
+        "Total in base currency",
+    ])];
+    for currency in currencies {
+        let in_currency: Vec<Transaction> = transactions
+            .iter()
+            .filter(|transaction| transaction.amount.currency == currency)
+            .cloned()
+            .collect();
+        let income: f64 = in_currency
+            .iter()
+            .map(|transaction| transaction.amount.value)
+            .filter(|value| *value > 0.0)
+            .sum();
+        let expenses: f64 = in_currency
+            .iter()
+            .map(|transaction| transaction.amount.value)
+            .filter(|value| *value < 0.0)
+            .sum();
+
+        rows.push(vec![
+            Cell::Text(currency.iso.clone()),
+            Cell::Number(in_currency.len() as f64),
+            Cell::Money(income, currency.clone()),
+            Cell::Money(expenses, currency.clone()),
+            Cell::Money(income + expenses, currency),
+            Cell::Money(Calculator::sum(&in_currency), Currency::base()),
+        ]);
+    }
+
+    Sheet {
+        name: "Currencies",
+        rows,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::transaction::amount::Amount;
+
+    pub(crate) fn transactions() -> Vec<Transaction> {
+        let transaction = |month: u32, value: f64, currency: Currency, base: f64| {
+            Transaction::new(
+                NaiveDate::from_ymd_opt(2024, month, 10).unwrap(),
+                Amount::new(value, currency),
+                Some(Amount::new(base, Currency::base())),
+                if value < 0.0 {
+                    TransactionType::Eat
+                } else {
+                    TransactionType::Unknown
+                },
+                Some("Note".to_owned()),
+            )
+        };
+
+        vec![
+            transaction(1, -10.0, Currency::eur(), -10.0),
+            transaction(1, -20.0, Currency::usd(), -18.0),
+            transaction(1, 100.0, Currency::eur(), 100.0),
+            transaction(3, -5.0, Currency::eur(), -5.0),
+        ]
+    }
+
+    #[test]
+    fn types_per_month() {
+        let sheet = &sheets(&transactions())[1];
+        let money = |value: f64| Cell::Money(value, Currency::base());
+
+        let food = sheet
+            .rows
+            .iter()
+            .find(|row| row[0] == Cell::Text(TransactionType::Eat.to_string()))
+            .unwrap();
+        assert_eq!(food[1], money(-28.0));
+        assert_eq!(food[3], money(-5.0));
+        assert_eq!(food[13], money(-33.0));
+
+        let balance = sheet.rows.last().unwrap();
+        assert_eq!(balance[0], Cell::Text("Balance".to_owned()));
+        assert_eq!(balance[1], money(72.0));
+        assert_eq!(balance[13], money(67.0));
+    }
+
+    #[test]
+    fn currencies() {
+        let sheet = &sheets(&transactions())[2];
+
+        assert_eq!(
+            sheet.rows[2],
+            vec![
+                Cell::Text("USD".to_owned()),
+                Cell::Number(1.0),
+                Cell::Money(0.0, Currency::usd()),
+                Cell::Money(-20.0, Currency::usd()),
+                Cell::Money(-20.0, Currency::usd()),
+                Cell::Money(-18.0, Currency::base()),
+            ]
+        );
+        assert_eq!(sheet.currencies(), vec![Currency::eur(), Currency::usd()]);
+    }
+}
@@ -0,0 +1,183 @@
+use super::{
+    spreadsheet::{self, Cell},
+    ExportOptions,
+};
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    transaction::Transaction,
+};
+use chrono::Datelike;
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, XlsxError};
+use std::io::Write;
+
+const DATE_FORMAT: &str = "yyyy-mm-dd";
+
+/// Write the report workbook in the Office Open XML format
+pub fn write<W: Write>(
+    writer: &mut W,
+    transactions: &[Transaction],
+    _options: &ExportOptions,
+) -> Res<()> {
+    let buffer = workbook(transactions).map_err(xlsx_error)?;
+    writer.write_all(&buffer)?;
+
+    Ok(())
+}
+
+fn xlsx_error(error: XlsxError) -> Error {
+    Error::FileIO(format!("Could not write XLSX: {}", error))
+}
+
+fn workbook(transactions: &[Transaction]) -> Result<Vec<u8>, XlsxError> {
+    let header_format = Format::new().set_bold();
+    let date_format = Format::new().set_num_format(DATE_FORMAT);
+
+    let mut workbook = Workbook::new();
+    for sheet in spreadsheet::sheets(transactions) {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(sheet.name)?;
+        worksheet.set_freeze_panes(1, 0)?;
+
+        for (row_index, row) in sheet.rows.iter().enumerate() {
+            let row_number = row_index as u32;
+            for (column_index, cell) in row.iter().enumerate() {
+                let column = column_index as u16;
+                match cell {
+                    Cell::Empty => {}
+                    Cell::Text(text) if row_index == 0 => {
+                        worksheet.write_string_with_format(
+                            row_number,
+                            column,
+                            text,
+                            &header_format,
+                        )?;
+                    }
+                    Cell::Text(text) => {
+                        worksheet.write_string(row_number, column, text)?;
+                    }
+                    Cell::Date(date) => {
+                        let date = ExcelDateTime::from_ymd(
+                            date.year() as u16,
+                            date.month() as u8,
+                            date.day() as u8,
+                        )?;
+                        worksheet.write_datetime_with_format(
+                            row_number,
+                            column,
+                            &date,
+                            &date_format,
+                        )?;
+                    }
+                    Cell::Number(value) => {
+                        worksheet.write_number(row_number, column, *value)?;
+                    }
+                    Cell::Money(value, currency) => {
+                        worksheet.write_number_with_format(
+                            row_number,
+                            column,
+                            *value,
+                            &currency_format(currency),
+                        )?;
+                    }
+                }
+            }
+        }
+        worksheet.autofit();
+    }
+
+    workbook.save_to_buffer()
+}
+
+/// Two decimals with the currency symbol, negative values in red
+fn currency_format(currency: &Currency) -> Format {
+    let symbol = currency.symbol.replace('"', "");
+
+    Format::new().set_num_format(format!("#,##0.00 \"{symbol}\";[Red]-#,##0.00 \"{symbol}\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    const NAMESPACE: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+
+    fn read_entry(archive: &mut zip::ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+
+        content
+    }
+
+    #[test]
+    fn write_workbook() {
+        let mut output = vec![];
+        write(
+            &mut output,
+            &spreadsheet::tests::transactions(),
+            &ExportOptions::default(),
+        )
+        .unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(output)).unwrap();
+
+        let workbook = read_entry(&mut archive, "xl/workbook.xml");
+        let workbook = roxmltree::Document::parse(&workbook).unwrap();
+        let sheet_names: Vec<&str> = workbook
+            .descendants()
+            .filter(|node| node.has_tag_name((NAMESPACE, "sheet")))
+            .filter_map(|node| node.attribute("name"))
+            .collect();
+        assert_eq!(
+            sheet_names,
+            vec!["Transactions", "Types per month", "Currencies"]
+        );
+
+        // The amount of the first transaction is a number with the euro format
+        let sheet = read_entry(&mut archive, "xl/worksheets/sheet1.xml");
+        let sheet = roxmltree::Document::parse(&sheet).unwrap();
+        let cell = sheet
+            .descendants()
+            .find(|node| node.attribute("r") == Some("D2"))
+            .unwrap();
+        assert_eq!(cell.attribute("t"), None);
+        assert_eq!(
+            cell.children()
+                .find(|node| node.has_tag_name((NAMESPACE, "v")))
+                .and_then(|node| node.text()),
+            Some("-10")
+        );
+
+        let styles = read_entry(&mut archive, "xl/styles.xml");
+        let styles = roxmltree::Document::parse(&styles).unwrap();
+        let style_index: usize = cell.attribute("s").unwrap().parse().unwrap();
+        let number_format = styles
+            .descendants()
+            .find(|node| node.has_tag_name((NAMESPACE, "cellXfs")))
+            .unwrap()
+            .children()
+            .filter(|node| node.is_element())
+            .nth(style_index)
+            .and_then(|node| node.attribute("numFmtId"))
+            .unwrap();
+        let format_code = styles
+            .descendants()
+            .find(|node| {
+                node.has_tag_name((NAMESPACE, "numFmt"))
+                    && node.attribute("numFmtId") == Some(number_format)
+            })
+            .and_then(|node| node.attribute("formatCode"))
+            .unwrap();
+        assert_eq!(
+            format_code,
+            format!(
+                "#,##0.00 \"{symbol}\";[Red]-#,##0.00 \"{symbol}\"",
+                symbol = Currency::eur().symbol
+            )
+        );
+    }
+}
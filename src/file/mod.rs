@@ -1,4 +1,5 @@
 use crate::error::{Error, Res};
+use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 use std::{env, fs};
 
@@ -35,6 +36,54 @@ pub fn normalize_file_path<P: AsRef<Path>>(path_input: P) -> Res<PathBuf> {
     }
 }
 
+/// SHA-256 of the file's content as lowercase hex
+pub fn content_hash<P: AsRef<Path>>(path: P) -> Res<String> {
+    let digest = Sha256::digest(fs::read(path)?);
+
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Expand directories and glob patterns into the files they contain
+///
+/// Directories are not searched recursively, only files for which `keep` returns true are taken
+/// from them. Other paths are returned unchanged, so missing files are reported by the caller.
+pub fn expand_paths<F>(paths: &[PathBuf], keep: F) -> Res<Vec<PathBuf>>
+where
+    F: Fn(&Path) -> bool,
+{
+    let mut expanded = vec![];
+    for path in paths {
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.retain(|entry| entry.is_file() && keep(entry));
+            entries.sort();
+            expanded.extend(entries);
+        } else if !path.exists() && is_glob_pattern(path) {
+            let pattern = path.to_string_lossy();
+            let mut matches = glob::glob(&pattern)
+                .map_err(|e| Error::Argument(format!("Invalid pattern {}: {}", pattern, e)))?
+                .filter_map(Result::ok)
+                .filter(|entry| entry.is_file())
+                .collect::<Vec<_>>();
+            if matches.is_empty() {
+                return Err(Error::file_io(format!("No files match {}", pattern)));
+            }
+            matches.sort();
+            expanded.extend(matches);
+        } else {
+            expanded.push(path.clone());
+        }
+    }
+
+    Ok(expanded)
+}
+
+fn is_glob_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
 fn prepend_current_working_directory(path: &&Path) -> Res<PathBuf> {
     match env::current_dir() {
         Ok(cwd) => normalize_file_path(format!("{}/{}", cwd.display(), path.display())),
@@ -76,4 +125,42 @@ mod tests {
     fn test_normalize_path_directory() {
         assert!(normalize_file_path(env!("CARGO_MANIFEST_DIR")).is_err());
     }
+
+    #[test]
+    fn test_content_hash() {
+        let path = std::env::temp_dir().join("budgeteer-content-hash-test.txt");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            content_hash(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_expand_paths() {
+        let directory = std::env::temp_dir().join("budgeteer-expand-paths-test");
+        fs::create_dir_all(directory.join("nested")).unwrap();
+        for name in ["b.csv", "a.csv", "notes.txt"] {
+            fs::write(directory.join(name), "").unwrap();
+        }
+
+        let is_csv = |path: &Path| path.extension().is_some_and(|e| e == "csv");
+        let expected = vec![directory.join("a.csv"), directory.join("b.csv")];
+        assert_eq!(
+            expand_paths(std::slice::from_ref(&directory), is_csv).unwrap(),
+            expected
+        );
+        assert_eq!(
+            expand_paths(&[directory.join("*.csv")], is_csv).unwrap(),
+            expected
+        );
+        assert_eq!(
+            expand_paths(&[PathBuf::from("-")], is_csv).unwrap(),
+            vec![PathBuf::from("-")]
+        );
+        assert!(expand_paths(&[directory.join("*.qif")], is_csv).is_err());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
 }
@@ -0,0 +1,316 @@
+use super::{scoped_external_id, ImportFormat, ImportResult, RowCollector};
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    transaction::{amount::Amount, transaction_type::TransactionType, Transaction},
+};
+use chrono::NaiveDate;
+use roxmltree::{Document, Node};
+use std::{fs, path::Path, str::FromStr};
+
+/// Import the entries of an ISO 20022 CAMT.053 bank statement
+///
+/// CAMT.052 and CAMT.054 documents use the same entry structure and are read as well
+pub fn get_transactions<T, P: AsRef<Path>>(
+    input_file: P,
+    rows: RowCollector<T>,
+) -> Result<ImportResult, Error>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let content = fs::read_to_string(input_file)?;
+
+    parse(&content, rows)
+}
+
+fn parse<T>(content: &str, mut rows: RowCollector<T>) -> Res<ImportResult>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let document = Document::parse(content)
+        .map_err(|e| Error::Import(format!("Could not parse CAMT XML: {}", e)))?;
+
+    let entries = document
+        .descendants()
+        .filter(|node| is_element(node, "Ntry"));
+    for (index, entry) in entries.enumerate() {
+        match build_transactions(entry) {
+            Ok(entry_transactions) => {
+                for transaction in entry_transactions {
+                    rows.push(Ok(transaction));
+                }
+            }
+            Err(e) => rows.push(Err(Error::Parse(format!(
+                "Entry {}: {}",
+                index + 1,
+                e.message()
+            )))),
+        }
+        if rows.is_full() {
+            break;
+        }
+    }
+
+    Ok(rows.finish())
+}
+
+/// Build the transactions of a single `<Ntry>`
+///
+/// Batch bookings list every single transaction in their own `<TxDtls>`. Each of them is
+/// imported as a separate transaction if it carries its own amount
+fn build_transactions(entry: Node) -> Res<Vec<Transaction>> {
+    let date = read_booking_date(entry)?;
+    // `NtryRef` only numbers the entries of a statement, so it cannot identify a transaction
+    let account = entry.parent().and_then(read_account);
+    let external_id = |reference: &str| scoped_external_id(ImportFormat::Camt, account, reference);
+    let reference = text(find(entry, &["AcctSvcrRef"]));
+    let details: Vec<Node> = entry
+        .descendants()
+        .filter(|node| is_element(node, "TxDtls"))
+        .collect();
+
+    let is_batch = details.len() > 1 && details.iter().all(|d| find_amount(*d).is_some());
+    if !is_batch {
+        let amount = read_amount(entry, entry)?;
+        let note = build_note(details.first().copied(), entry, amount.value);
+        let transaction = Transaction::new(date, amount, None, TransactionType::Unknown, note);
+
+        return Ok(vec![match reference {
+            Some(reference) => transaction.with_external_id(external_id(reference)),
+            None => transaction,
+        }]);
+    }
+
+    details
+        .into_iter()
+        .enumerate()
+        .map(|(index, detail)| {
+            let amount = read_amount(detail, entry)?;
+            let note = build_note(Some(detail), entry, amount.value);
+            let transaction = Transaction::new(date, amount, None, TransactionType::Unknown, note);
+
+            let detail_reference = text(find(detail, &["Refs", "AcctSvcrRef"]));
+            Ok(match (detail_reference, reference) {
+                (Some(detail_reference), _) => {
+                    transaction.with_external_id(external_id(detail_reference))
+                }
+                (None, Some(reference)) => transaction.with_external_id(external_id(&format!(
+                    "{}/{}",
+                    reference,
+                    index + 1
+                ))),
+                (None, None) => transaction,
+            })
+        })
+        .collect()
+}
+
+/// Read the IBAN (or other identification) of the account a statement belongs to
+fn read_account<'a>(statement: Node<'a, '_>) -> Option<&'a str> {
+    text(find(statement, &["Acct", "Id", "IBAN"]))
+        .or_else(|| text(find(statement, &["Acct", "Id", "Othr", "Id"])))
+}
+
+fn read_booking_date(entry: Node) -> Res<NaiveDate> {
+    let raw_date = text(find(entry, &["BookgDt", "Dt"]))
+        .or_else(|| text(find(entry, &["BookgDt", "DtTm"])))
+        .or_else(|| text(find(entry, &["ValDt", "Dt"])))
+        .ok_or_else(|| Error::Parse("Entry has no booking date".to_owned()))?;
+
+    // `DtTm` holds a full timestamp, the date is always the first 10 characters
+    let raw_date = raw_date.get(..10).unwrap_or(raw_date);
+    NaiveDate::parse_from_str(raw_date, "%Y-%m-%d")
+        .map_err(|e| Error::Parse(format!("Could not parse date '{}': {}", raw_date, e)))
+}
+
+/// Read the signed amount of `node`, the credit/debit indicator may be inherited from `entry`
+fn read_amount(node: Node, entry: Node) -> Res<Amount> {
+    let amount_node =
+        find_amount(node).ok_or_else(|| Error::Parse("Entry has no amount".to_owned()))?;
+    let raw_value = text(Some(amount_node)).unwrap_or_default();
+    let value = raw_value
+        .parse::<f64>()
+        .map_err(|e| Error::Parse(format!("Could not parse amount '{}': {}", raw_value, e)))?;
+    let currency = Currency::from_str(amount_node.attribute("Ccy").unwrap_or_default())?;
+
+    let indicator = text(find(node, &["CdtDbtInd"]))
+        .or_else(|| text(find(entry, &["CdtDbtInd"])))
+        .unwrap_or_default();
+    let value = match indicator {
+        "CRDT" => value.abs(),
+        "DBIT" => -value.abs(),
+        _ => {
+            return Err(Error::Parse(format!(
+                "Invalid credit/debit indicator '{}'",
+                indicator
+            )))
+        }
+    };
+
+    Ok(Amount::new(value, currency))
+}
+
+fn find_amount<'a, 'i>(node: Node<'a, 'i>) -> Option<Node<'a, 'i>> {
+    find(node, &["Amt"]).or_else(|| find(node, &["AmtDtls", "TxAmt", "Amt"]))
+}
+
+/// Build the note from the counterparty and the remittance information
+fn build_note(detail: Option<Node>, entry: Node, value: f64) -> Option<String> {
+    // For expenses the counterparty is the creditor, for income it is the debtor
+    let party = if value < 0.0 { "Cdtr" } else { "Dbtr" };
+    let counterparty = detail
+        .and_then(|detail| find(detail, &["RltdPties", party]))
+        .and_then(|node| {
+            node.descendants()
+                .find(|n| is_element(n, "Nm"))
+                .and_then(|n| text(Some(n)))
+        });
+
+    let unstructured: Vec<&str> = detail
+        .and_then(|detail| find(detail, &["RmtInf"]))
+        .map(|remittance| {
+            remittance
+                .children()
+                .filter(|n| is_element(n, "Ustrd"))
+                .filter_map(|n| text(Some(n)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let remittance = if !unstructured.is_empty() {
+        Some(unstructured.join(" "))
+    } else {
+        detail
+            .and_then(|detail| {
+                text(find(detail, &["RmtInf", "Strd", "CdtrRefInf", "Ref"]))
+                    .or_else(|| text(find(detail, &["AddtlTxInf"])))
+            })
+            .or_else(|| text(find(entry, &["AddtlNtryInf"])))
+            .map(str::to_owned)
+    };
+
+    let parts: Vec<String> = counterparty
+        .map(str::to_owned)
+        .into_iter()
+        .chain(remittance)
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(" - "))
+    }
+}
+
+fn is_element(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+/// Follow the element `path` below `node` (namespaces are ignored)
+fn find<'a, 'i>(node: Node<'a, 'i>, path: &[&str]) -> Option<Node<'a, 'i>> {
+    path.iter().try_fold(node, |current, name| {
+        current.children().find(|child| is_element(child, name))
+    })
+}
+
+fn text<'a>(node: Option<Node<'a, '_>>) -> Option<&'a str> {
+    node.and_then(|n| n.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATEMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Acct><Id><IBAN>DE89370400440532013000</IBAN></Id></Acct>
+      <Ntry>
+        <NtryRef>1</NtryRef>
+        <Amt Ccy="EUR">45.90</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <BookgDt><Dt>2024-03-04</Dt></BookgDt>
+        <AcctSvcrRef>2024030412345</AcctSvcrRef>
+        <NtryDtls>
+          <TxDtls>
+            <RltdPties><Cdtr><Nm>Stadtwerke Musterstadt</Nm></Cdtr></RltdPties>
+            <RmtInf><Ustrd>Abschlag Strom</Ustrd><Ustrd>Maerz 2024</Ustrd></RmtInf>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">2500.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><DtTm>2024-03-25T10:00:00</DtTm></BookgDt>
+        <AcctSvcrRef>2024032500001</AcctSvcrRef>
+        <NtryDtls>
+          <TxDtls>
+            <RltdPties><Dbtr><Pty><Nm>Employer AG</Nm></Pty></Dbtr></RltdPties>
+            <RmtInf><Ustrd>Salary March</Ustrd></RmtInf>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">30.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <BookgDt><Dt>2024-03-28</Dt></BookgDt>
+        <AcctSvcrRef>BATCH-1</AcctSvcrRef>
+        <NtryDtls>
+          <TxDtls>
+            <AmtDtls><TxAmt><Amt Ccy="EUR">10.00</Amt></TxAmt></AmtDtls>
+            <RmtInf><Ustrd>First</Ustrd></RmtInf>
+          </TxDtls>
+          <TxDtls>
+            <AmtDtls><TxAmt><Amt Ccy="EUR">20.00</Amt></TxAmt></AmtDtls>
+            <RmtInf><Ustrd>Second</Ustrd></RmtInf>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">1.00</Amt>
+        <BookgDt><Dt>2024-03-29</Dt></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+
+    #[test]
+    fn parse_statement() {
+        let result = parse(STATEMENT, RowCollector::new(|t| Ok(Some(t)), None)).unwrap();
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.transactions.len(), 4);
+
+        let expense = &result.transactions[0];
+        assert_eq!(expense.amount, Amount::new(-45.9, Currency::eur()));
+        assert_eq!(expense.date, NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(
+            expense.note,
+            Some("Stadtwerke Musterstadt - Abschlag Strom Maerz 2024".to_owned())
+        );
+        assert_eq!(
+            expense.external_id,
+            Some("camt:DE89370400440532013000:2024030412345".to_owned())
+        );
+
+        let income = &result.transactions[1];
+        assert_eq!(income.amount, Amount::new(2500.0, Currency::chf()));
+        assert_eq!(income.date, NaiveDate::from_ymd_opt(2024, 3, 25).unwrap());
+        assert_eq!(income.note, Some("Employer AG - Salary March".to_owned()));
+
+        assert_eq!(result.transactions[2].amount.value, -10.0);
+        assert_eq!(
+            result.transactions[3].external_id,
+            Some("camt:DE89370400440532013000:BATCH-1/2".to_owned())
+        );
+    }
+
+    #[test]
+    fn entry_reference_is_no_external_id() {
+        let statement = STATEMENT.replace("<AcctSvcrRef>2024030412345</AcctSvcrRef>", "");
+        let result = parse(&statement, RowCollector::new(|t| Ok(Some(t)), None)).unwrap();
+
+        assert_eq!(result.transactions[0].external_id, None);
+    }
+}
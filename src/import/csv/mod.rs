@@ -0,0 +1,394 @@
+mod profile;
+
+pub use self::profile::{Column, CsvProfile};
+use super::{ImportResult, RowCollector};
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    transaction::{amount::Amount, transaction_type::TransactionType, Transaction},
+};
+use chrono::NaiveDate;
+use csv::StringRecord;
+use encoding_rs::Encoding;
+use std::{fs, path::Path, str::FromStr};
+
+pub fn get_transactions<T, P: AsRef<Path>>(
+    input_file: P,
+    profile: &CsvProfile,
+    rows: RowCollector<T>,
+) -> Result<ImportResult, Error>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let bytes = fs::read(input_file)?;
+    let content = decode(&bytes, &profile.encoding)?;
+
+    parse(&content, profile, rows)
+}
+
+fn decode(bytes: &[u8], encoding: &str) -> Res<String> {
+    let encoding = Encoding::for_label(encoding.as_bytes())
+        .ok_or_else(|| Error::Import(format!("Unknown encoding '{}'", encoding)))?;
+    let (content, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        return Err(Error::Import(format!(
+            "File contains characters that are invalid in {}",
+            encoding.name()
+        )));
+    }
+
+    Ok(content.into_owned())
+}
+
+fn parse<T>(content: &str, profile: &CsvProfile, mut rows: RowCollector<T>) -> Res<ImportResult>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let content = content
+        .split_inclusive('\n')
+        .skip(profile.skip_lines)
+        .collect::<String>();
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(ascii_byte(profile.delimiter, "delimiter")?)
+        .quote(ascii_byte(profile.quote, "quote")?)
+        .has_headers(profile.has_headers)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let headers = if profile.has_headers {
+        Some(
+            reader
+                .headers()
+                .map_err(|e| Error::Import(format!("Could not read CSV header: {}", e)))?
+                .clone(),
+        )
+    } else {
+        None
+    };
+    let mapping = ColumnMapping::new(profile, headers.as_ref())?;
+
+    let first_row = profile.skip_lines + if profile.has_headers { 2 } else { 1 };
+    for (index, record) in reader.records().enumerate() {
+        let row = first_row + index;
+        let transaction = record
+            .map_err(|e| Error::Parse(format!("Could not read CSV row: {}", e)))
+            .and_then(|record| mapping.build_transaction(&record))
+            .map_err(|e| Error::Parse(format!("Row {}: {}", row, e.message())));
+
+        rows.push(transaction);
+        if rows.is_full() {
+            break;
+        }
+    }
+
+    Ok(rows.finish())
+}
+
+fn ascii_byte(c: char, name: &str) -> Res<u8> {
+    if c.is_ascii() {
+        Ok(c as u8)
+    } else {
+        Err(Error::Import(format!(
+            "CSV {} must be an ASCII character",
+            name
+        )))
+    }
+}
+
+/// Column positions resolved from the profile and the header row
+struct ColumnMapping<'a> {
+    profile: &'a CsvProfile,
+    date: usize,
+    amount: Option<usize>,
+    debit: Option<usize>,
+    credit: Option<usize>,
+    debit_credit: Option<usize>,
+    currency: Option<usize>,
+    note: Vec<usize>,
+    transaction_type: Option<usize>,
+}
+
+impl<'a> ColumnMapping<'a> {
+    fn new(profile: &'a CsvProfile, headers: Option<&StringRecord>) -> Res<Self> {
+        let columns = &profile.columns;
+        let resolve_optional = |column: &Option<Column>| -> Option<usize> {
+            column
+                .as_ref()
+                .and_then(|column| resolve_column(column, headers))
+        };
+
+        let date = resolve_required(&columns.date, headers, "date")?;
+        let amount = resolve_optional(&columns.amount);
+        let debit = resolve_optional(&columns.debit);
+        let credit = resolve_optional(&columns.credit);
+        if amount.is_none() && debit.is_none() && credit.is_none() {
+            return Err(Error::Import(format!(
+                "CSV profile '{}' defines neither an amount nor a debit/credit column that exists in the file",
+                profile.name
+            )));
+        }
+
+        Ok(Self {
+            profile,
+            date,
+            amount,
+            debit,
+            credit,
+            debit_credit: resolve_optional(&columns.debit_credit),
+            currency: resolve_optional(&columns.currency),
+            note: columns
+                .note
+                .iter()
+                .filter_map(|column| resolve_column(column, headers))
+                .collect(),
+            transaction_type: resolve_optional(&columns.r#type),
+        })
+    }
+
+    fn build_transaction(&self, record: &StringRecord) -> Res<Transaction> {
+        let raw_date = self.get(record, self.date).unwrap_or_default();
+        let date = NaiveDate::parse_from_str(raw_date, &self.profile.date_format)
+            .map_err(|e| Error::Parse(format!("Could not parse date '{}': {}", raw_date, e)))?;
+
+        let currency = match self.currency.and_then(|i| self.get(record, i)) {
+            Some(raw_currency) => Currency::from_str(raw_currency)?,
+            None => Currency::from_str(&self.profile.default_currency)?,
+        };
+
+        let transaction_type = self
+            .transaction_type
+            .and_then(|i| self.get(record, i))
+            .map_or(TransactionType::Unknown, TransactionType::from_str);
+
+        let note = self
+            .note
+            .iter()
+            .filter_map(|i| self.get(record, *i))
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        Ok(Transaction::new(
+            date,
+            Amount::new(self.read_amount(record)?, currency),
+            None,
+            transaction_type,
+            Some(note).filter(|note| !note.is_empty()),
+        ))
+    }
+
+    fn read_amount(&self, record: &StringRecord) -> Res<f64> {
+        if let Some(raw_amount) = self.amount.and_then(|i| self.get(record, i)) {
+            let amount = self.parse_amount(raw_amount)?;
+            let amount = if self.profile.invert_amount {
+                -amount
+            } else {
+                amount
+            };
+
+            return Ok(match self.debit_credit.and_then(|i| self.get(record, i)) {
+                Some(indicator) if self.is_debit(indicator) => -amount.abs(),
+                Some(_) => amount.abs(),
+                None => amount,
+            });
+        }
+
+        if let Some(raw_debit) = self.debit.and_then(|i| self.get(record, i)) {
+            return Ok(-self.parse_amount(raw_debit)?.abs());
+        }
+        if let Some(raw_credit) = self.credit.and_then(|i| self.get(record, i)) {
+            return Ok(self.parse_amount(raw_credit)?.abs());
+        }
+
+        Err(Error::Parse("Could not read amount from row".to_owned()))
+    }
+
+    fn parse_amount(&self, raw_amount: &str) -> Res<f64> {
+        let mut prepared: String = raw_amount
+            .chars()
+            .filter(|c| !c.is_whitespace() && Some(*c) != self.profile.thousands_separator)
+            .map(|c| {
+                if c == self.profile.decimal_separator {
+                    '.'
+                } else {
+                    c
+                }
+            })
+            .collect();
+
+        // Some banks write negative numbers with a trailing minus (`12.50-`)
+        if let Some(stripped) = prepared.strip_suffix('-') {
+            prepared = format!("-{}", stripped);
+        }
+
+        prepared
+            .parse::<f64>()
+            .map_err(|e| Error::Parse(format!("Could not parse amount '{}': {}", raw_amount, e)))
+    }
+
+    fn is_debit(&self, indicator: &str) -> bool {
+        self.profile
+            .columns
+            .debit_indicators
+            .iter()
+            .any(|debit_indicator| debit_indicator.eq_ignore_ascii_case(indicator))
+    }
+
+    /// Return the non-empty value of the column at `index`
+    fn get<'r>(&self, record: &'r StringRecord, index: usize) -> Option<&'r str> {
+        record.get(index).filter(|value| !value.is_empty())
+    }
+}
+
+fn resolve_column(column: &Column, headers: Option<&StringRecord>) -> Option<usize> {
+    match column {
+        Column::Index(index) => Some(*index),
+        Column::Name(name) => headers?
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name.trim())),
+    }
+}
+
+fn resolve_required(column: &Column, headers: Option<&StringRecord>, name: &str) -> Res<usize> {
+    resolve_column(column, headers).ok_or_else(|| match column {
+        Column::Name(header) if headers.is_some() => Error::Import(format!(
+            "CSV file has no column '{}' for the {}",
+            header, name
+        )),
+        _ => Error::Import(format!(
+            "The {} column must be given as a position if the CSV file has no header",
+            name
+        )),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::csv::profile::CsvColumns;
+
+    fn keep(transaction: Transaction) -> Res<Option<Transaction>> {
+        Ok(Some(transaction))
+    }
+
+    #[test]
+    fn parse_with_default_profile() {
+        let content = "date,amount,currency,note,type\n\
+                       2024-01-15,-12.50,CHF,\"Coop, Bern\",E\n\
+                       2024-01-16,100,EUR,Refund,\n\
+                       2024-01-17,-5,CHF, ,\n";
+        let result = parse(
+            content,
+            &CsvProfile::default(),
+            RowCollector::new(keep, None),
+        )
+        .unwrap();
+
+        assert!(result.errors.is_empty());
+        assert_eq!(result.transactions.len(), 3);
+        let transaction = &result.transactions[0];
+        assert_eq!(transaction.amount, Amount::new(-12.5, Currency::chf()));
+        assert_eq!(transaction.transaction_type, TransactionType::Eat);
+        assert_eq!(transaction.note, Some("Coop, Bern".to_owned()));
+        assert_eq!(
+            result.transactions[1].transaction_type,
+            TransactionType::Unknown
+        );
+        assert_eq!(result.transactions[2].note, None);
+    }
+
+    #[test]
+    fn parse_with_german_profile() {
+        let profile = CsvProfile {
+            delimiter: ';',
+            skip_lines: 2,
+            date_format: "%d.%m.%Y".to_owned(),
+            decimal_separator: ',',
+            thousands_separator: Some('.'),
+            columns: CsvColumns {
+                date: Column::Name("Buchungstag".to_owned()),
+                amount: Some(Column::Name("Betrag".to_owned())),
+                debit_credit: Some(Column::Index(3)),
+                note: vec![
+                    Column::Name("Empfänger".to_owned()),
+                    Column::Name("Verwendungszweck".to_owned()),
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let content = "Kontoauszug\n\n\
+                       Buchungstag;Empfänger;Betrag;S/H;Verwendungszweck\n\
+                       02.01.2024;Stadtwerke;1.234,56;S;Strom\n\
+                       03.01.2024;Arbeitgeber;2.000,00;H;Gehalt\n\
+                       xx.01.2024;Broken;1,00;S;\n";
+        let result = parse(content, &profile, RowCollector::new(keep, None)).unwrap();
+
+        assert_eq!(result.transactions.len(), 2);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(
+            result.transactions[0].amount,
+            Amount::new(-1234.56, Currency::eur())
+        );
+        assert_eq!(
+            result.transactions[0].note,
+            Some("Stadtwerke Strom".to_owned())
+        );
+        assert_eq!(result.transactions[1].amount.value, 2000.0);
+        assert!(result.errors[0].to_string().contains("Row 6"));
+    }
+
+    #[test]
+    fn parse_debit_and_credit_columns() {
+        let profile = CsvProfile {
+            has_headers: false,
+            columns: CsvColumns {
+                date: Column::Index(0),
+                amount: None,
+                debit: Some(Column::Index(1)),
+                credit: Some(Column::Index(2)),
+                note: vec![Column::Index(3)],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let content = "2024-02-01,45.00,,Groceries\n2024-02-02,,10.00,Refund\n";
+        let result = parse(content, &profile, RowCollector::new(keep, None)).unwrap();
+
+        assert!(result.errors.is_empty());
+        assert_eq!(result.transactions[0].amount.value, -45.0);
+        assert_eq!(result.transactions[1].amount.value, 10.0);
+    }
+
+    #[test]
+    fn stop_after_max_errors() {
+        let content = "date,amount,currency\n\
+                       2024-01-15,abc,EUR\n\
+                       2024-01-16,-1,EUR\n\
+                       2024-01-17,def,EUR\n\
+                       2024-01-18,-2,EUR\n";
+        let mut prepared = 0;
+        let rows = RowCollector::new(
+            |transaction| {
+                prepared += 1;
+                Ok(Some(transaction))
+            },
+            Some(2),
+        );
+        let result = parse(content, &CsvProfile::default(), rows).unwrap();
+
+        assert_eq!(result.errors.len(), 2);
+        assert_eq!(result.transactions.len(), 1);
+        assert_eq!(prepared, 1);
+    }
+
+    #[test]
+    fn decode_latin1() {
+        assert_eq!(decode(b"Caf\xe9", "windows-1252").unwrap(), "Café");
+        assert!(decode(b"Caf\xe9", "utf-8").is_err());
+        assert!(decode(b"Cafe", "not-an-encoding").is_err());
+    }
+}
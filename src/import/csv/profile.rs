@@ -0,0 +1,174 @@
+use crate::error::{Error, Res};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Describes how the rows of a bank's CSV export map to transactions
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CsvProfile {
+    pub name: String,
+    pub delimiter: char,
+    pub quote: char,
+    /// Label of the file's character encoding (e.g. `utf-8`, `windows-1252`, `iso-8859-15`)
+    pub encoding: String,
+    pub has_headers: bool,
+    /// Number of lines to ignore before the header (or first row)
+    pub skip_lines: usize,
+    /// Format of the date column in `chrono` syntax
+    pub date_format: String,
+    pub decimal_separator: char,
+    pub thousands_separator: Option<char>,
+    /// Currency to use if the profile has no currency column
+    pub default_currency: String,
+    /// Invert the sign of the amount column (for exports that list expenses as positive values)
+    pub invert_amount: bool,
+    pub columns: CsvColumns,
+}
+
+/// Column assignment of a `CsvProfile`
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CsvColumns {
+    pub date: Column,
+    /// Column holding the signed amount
+    pub amount: Option<Column>,
+    /// Column holding expenses (used instead of `amount`)
+    pub debit: Option<Column>,
+    /// Column holding income (used instead of `amount`)
+    pub credit: Option<Column>,
+    /// Column that marks the amount as debit or credit
+    pub debit_credit: Option<Column>,
+    /// Values of the `debit_credit` column that mark an expense
+    pub debit_indicators: Vec<String>,
+    pub currency: Option<Column>,
+    /// Columns that are joined to build the note
+    pub note: Vec<Column>,
+    pub r#type: Option<Column>,
+}
+
+/// Reference to a column either by its header name or its zero-based position
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl Default for CsvProfile {
+    fn default() -> Self {
+        Self {
+            name: "default".to_owned(),
+            delimiter: ',',
+            quote: '"',
+            encoding: "utf-8".to_owned(),
+            has_headers: true,
+            skip_lines: 0,
+            date_format: "%Y-%m-%d".to_owned(),
+            decimal_separator: '.',
+            thousands_separator: None,
+            default_currency: "EUR".to_owned(),
+            invert_amount: false,
+            columns: CsvColumns::default(),
+        }
+    }
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            date: Column::Name("date".to_owned()),
+            amount: Some(Column::Name("amount".to_owned())),
+            debit: None,
+            credit: None,
+            debit_credit: None,
+            debit_indicators: vec!["D".to_owned(), "S".to_owned(), "DEBIT".to_owned()],
+            currency: Some(Column::Name("currency".to_owned())),
+            note: vec![Column::Name("note".to_owned())],
+            r#type: Some(Column::Name("type".to_owned())),
+        }
+    }
+}
+
+impl CsvProfile {
+    /// Load the profile `name`
+    ///
+    /// `name` may be the path of a JSON profile file or the name of a file inside the `profiles`
+    /// directory next to the database
+    pub fn load(name: &str, database: &Path) -> Res<Self> {
+        let path = Self::find(name, database)?;
+        let file = File::open(&path)?;
+
+        let mut profile: CsvProfile =
+            serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+                Error::Import(format!(
+                    "Could not read CSV profile {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        if profile.name.is_empty() || profile.name == "default" {
+            profile.name = name.to_owned();
+        }
+
+        Ok(profile)
+    }
+
+    fn find(name: &str, database: &Path) -> Res<PathBuf> {
+        let path = PathBuf::from(name);
+        if path.is_file() {
+            return Ok(path);
+        }
+
+        let profile_path = database
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("profiles")
+            .join(format!("{}.json", name));
+        if profile_path.is_file() {
+            Ok(profile_path)
+        } else {
+            Err(Error::Import(format!(
+                "CSV profile '{}' not found (looked for {})",
+                name,
+                profile_path.display()
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_profile() {
+        let profile: CsvProfile = serde_json::from_str(
+            r#"{
+                "name": "bank",
+                "delimiter": ";",
+                "encoding": "windows-1252",
+                "date_format": "%d.%m.%Y",
+                "decimal_separator": ",",
+                "thousands_separator": ".",
+                "columns": {
+                    "date": "Buchungstag",
+                    "amount": 4,
+                    "note": ["Empfänger", "Verwendungszweck"]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(profile.delimiter, ';');
+        assert_eq!(profile.quote, '"');
+        assert_eq!(profile.columns.date, Column::Name("Buchungstag".to_owned()));
+        assert_eq!(profile.columns.amount, Some(Column::Index(4)));
+        assert_eq!(profile.columns.note.len(), 2);
+        assert_eq!(
+            profile.columns.currency,
+            Some(Column::Name("currency".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::error::Error;
+use std::fmt;
+
+/// Position of an imported value in its source file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceSpan {
+    pub file: Option<String>,
+    /// 1-based line number
+    pub line: Option<usize>,
+    /// 1-based column, counted in characters
+    pub column: Option<usize>,
+    /// Number of characters to underline
+    pub length: usize,
+    /// Path of the value in a JSON document, e.g. `$[3].amount`
+    pub json_path: Option<String>,
+}
+
+impl SourceSpan {
+    pub fn line(line: usize) -> Self {
+        Self {
+            line: Some(line),
+            ..Self::default()
+        }
+    }
+
+    pub fn json_path<S: Into<String>>(path: S) -> Self {
+        Self {
+            json_path: Some(path.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_column(self, column: usize, length: usize) -> Self {
+        Self {
+            column: Some(column),
+            length,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut location = self.file.clone().unwrap_or_default();
+        if let Some(line) = self.line {
+            if !location.is_empty() {
+                location.push(':');
+            }
+            location.push_str(&line.to_string());
+            if let Some(column) = self.column {
+                location.push_str(&format!(":{}", column));
+            }
+        }
+        if let Some(json_path) = &self.json_path {
+            if !location.is_empty() {
+                location.push(' ');
+            }
+            location.push_str(json_path);
+        }
+
+        write!(f, "{}", location)
+    }
+}
+
+/// An import error with the place it was found
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: SourceSpan,
+    /// Text of the offending line, shown below the message
+    pub source_line: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new<S: Into<String>>(message: S, span: SourceSpan) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            source_line: None,
+        }
+    }
+
+    pub fn with_source_line<S: Into<String>>(self, source_line: S) -> Self {
+        Self {
+            source_line: Some(source_line.into()),
+            ..self
+        }
+    }
+}
+
+impl From<Error> for Diagnostic {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Diagnostic(diagnostic) => *diagnostic,
+            error => Diagnostic::new(error.message(), SourceSpan::default()),
+        }
+    }
+}
+
+impl From<Diagnostic> for Error {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Error::Diagnostic(Box::new(diagnostic))
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// Render like a compiler error, with the offending line and a caret below the value
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error: {}", self.message)?;
+
+        let width = self.span.line.map_or(1, |line| line.to_string().len());
+        let location = self.span.to_string();
+        if !location.is_empty() {
+            write!(f, "\n{:width$}--> {}", "", location, width = width)?;
+        }
+        if let (Some(line), Some(source_line)) = (self.span.line, &self.source_line) {
+            write!(f, "\n{:width$} |", "", width = width)?;
+            write!(f, "\n{} | {}", line, source_line)?;
+            if let Some(column) = self.span.column {
+                write!(
+                    f,
+                    "\n{:width$} | {}{}",
+                    "",
+                    " ".repeat(column.saturating_sub(1)),
+                    "^".repeat(self.span.length.max(1)),
+                    width = width
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_with_source_line() {
+        let mut span = SourceSpan::line(4).with_column(16, 3);
+        span.file = Some("januar.md".to_owned());
+        let diagnostic = Diagnostic::new("Could not parse amount 'abc'", span)
+            .with_source_line("| 16.01.2024 | € | abc | E |");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: Could not parse amount 'abc'\n \
+             --> januar.md:4:16\n  \
+             |\n\
+             4 | | 16.01.2024 | € | abc | E |\n  \
+             |                ^^^"
+        );
+    }
+
+    #[test]
+    fn display_json_path() {
+        let mut span = SourceSpan::json_path("$[2].date");
+        span.file = Some("export.json".to_owned());
+        let diagnostic = Diagnostic::new("Field 'date': is missing", span);
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: Field 'date': is missing\n --> export.json $[2].date"
+        );
+        assert_eq!(
+            Diagnostic::new("Failed", SourceSpan::default()).to_string(),
+            "error: Failed"
+        );
+    }
+}
@@ -0,0 +1,505 @@
+use super::{ImportResult, RowCollector};
+use crate::{
+    category_mapping::CategoryMapping,
+    currency::Currency,
+    error::{Error, Res},
+    transaction::{amount::Amount, transaction_type::TransactionType, Transaction},
+};
+use chrono::NaiveDate;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{fs, path::Path, str::FromStr};
+
+lazy_static! {
+    static ref HEADER: Regex =
+        Regex::new(r"^(\d{4}[-/.]\d{1,2}[-/.]\d{1,2})(?:=\S+)?(?:\s+(.*))?$").unwrap();
+    static ref AMOUNT: Regex =
+        Regex::new(r"^(?:([^\d\s+\-.,]+)\s*)?([+-]?\s*[\d.,]*\d)(?:\s*([^\d\s+\-.,@{]+))?$")
+            .unwrap();
+}
+
+/// Accounts that hold money, postings to them are the funding side of a transaction
+const BALANCE_ACCOUNTS: [&str; 3] = ["Assets", "Liabilities", "Equity"];
+
+/// Beancount directives that start with a date but are no transactions
+const DATED_DIRECTIVES: [&str; 10] = [
+    "open",
+    "close",
+    "balance",
+    "pad",
+    "price",
+    "note",
+    "document",
+    "event",
+    "commodity",
+    "custom",
+];
+
+/// Import the transactions of a ledger, hledger or beancount journal
+///
+/// Every posting to an expense or income account becomes a transaction, its type is looked up
+/// in `category_mapping` by the account name. Postings to asset, liability and equity accounts
+/// are the funding side and are not imported
+pub fn get_transactions<T, P: AsRef<Path>>(
+    input_file: P,
+    category_mapping: &CategoryMapping,
+    rows: RowCollector<T>,
+) -> Result<ImportResult, Error>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let content = fs::read_to_string(input_file)?;
+
+    Ok(parse(&content, category_mapping, rows))
+}
+
+struct Entry {
+    line: usize,
+    date: NaiveDate,
+    description: Option<String>,
+    postings: Vec<Posting>,
+}
+
+struct Posting {
+    account: String,
+    amount: Option<Amount>,
+    /// Amount of the posting in the base currency, taken from an `@` or `@@` price
+    base_amount: Option<Amount>,
+}
+
+fn parse<T>(
+    content: &str,
+    category_mapping: &CategoryMapping,
+    mut rows: RowCollector<T>,
+) -> ImportResult
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let mut entry: Option<Res<Entry>> = None;
+    let finish_entry = |entry: Option<Res<Entry>>, rows: &mut RowCollector<T>| {
+        let result = match entry {
+            Some(Ok(entry)) => build_transactions(&entry, category_mapping).map_err(|e| {
+                Error::Parse(format!(
+                    "Transaction at line {}: {}",
+                    entry.line,
+                    e.message()
+                ))
+            }),
+            Some(Err(e)) => Err(e),
+            None => return,
+        };
+
+        match result {
+            Ok(built) => {
+                for transaction in built {
+                    rows.push(Ok(transaction));
+                }
+            }
+            Err(e) => rows.push(Err(e)),
+        }
+    };
+
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let line = strip_comment(line.trim_end_matches('\r'));
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let is_indented = line.starts_with(' ') || line.starts_with('\t');
+        if is_indented {
+            if let Some(Ok(current)) = entry.as_mut() {
+                let trimmed = line.trim();
+                // Posting metadata (`key: value`) and ledger tags carry no amounts
+                if is_metadata(trimmed) {
+                    continue;
+                }
+                match parse_posting(trimmed) {
+                    Ok(posting) => current.postings.push(posting),
+                    Err(e) => {
+                        entry = Some(Err(Error::Parse(format!(
+                            "Posting at line {}: {}",
+                            line_number,
+                            e.message()
+                        ))))
+                    }
+                }
+            }
+            continue;
+        }
+
+        finish_entry(entry.take(), &mut rows);
+        if rows.is_full() {
+            return rows.finish();
+        }
+        if let Some(captures) = HEADER.captures(line) {
+            let rest = captures.get(2).map_or("", |m| m.as_str()).trim();
+            if DATED_DIRECTIVES.contains(&rest.split_whitespace().next().unwrap_or_default()) {
+                continue;
+            }
+
+            entry = Some(parse_date(&captures[1]).map(|date| Entry {
+                line: line_number,
+                date,
+                description: parse_description(rest),
+                postings: vec![],
+            }));
+        }
+        // Other unindented lines are directives like `account`, `P` or `option`
+    }
+    finish_entry(entry.take(), &mut rows);
+
+    rows.finish()
+}
+
+fn build_transactions(entry: &Entry, category_mapping: &CategoryMapping) -> Res<Vec<Transaction>> {
+    let postings = complete_elided_amount(&entry.postings)?;
+
+    Ok(postings
+        .iter()
+        .filter(|(account, _, _)| !is_balance_account(account))
+        .map(|(account, amount, base_amount)| {
+            // The expense account increases when money is spent, in the budget this is negative
+            Transaction::new(
+                entry.date,
+                Amount::new(-amount.value, amount.currency.clone()),
+                base_amount
+                    .as_ref()
+                    .map(|b| Amount::new(-b.value, b.currency.clone())),
+                find_type(account, category_mapping),
+                entry.description.clone(),
+            )
+        })
+        .collect())
+}
+
+/// Calculate the amount of the single posting without an amount
+fn complete_elided_amount(postings: &[Posting]) -> Res<Vec<(&str, Amount, Option<Amount>)>> {
+    let elided = postings.iter().filter(|p| p.amount.is_none()).count();
+    if elided > 1 {
+        return Err(Error::Parse(
+            "Only one posting may leave out its amount".to_owned(),
+        ));
+    }
+
+    let mut sum: Option<Amount> = None;
+    for posting in postings {
+        // Postings with a price balance in the price's currency
+        let amount = match (&posting.base_amount, &posting.amount) {
+            (Some(base_amount), _) => base_amount,
+            (None, Some(amount)) => amount,
+            (None, None) => continue,
+        };
+        sum = match sum {
+            None => Some(amount.clone()),
+            Some(sum) if sum.currency == amount.currency => {
+                Some(Amount::new(sum.value + amount.value, sum.currency))
+            }
+            Some(_) if elided > 0 => {
+                return Err(Error::Parse(
+                    "Cannot calculate the missing amount of postings in several currencies"
+                        .to_owned(),
+                ))
+            }
+            Some(sum) => Some(sum),
+        };
+    }
+
+    postings
+        .iter()
+        .map(|posting| {
+            let amount = match (&posting.amount, &sum) {
+                (Some(amount), _) => amount.clone(),
+                (None, Some(sum)) => Amount::new(-sum.value, sum.currency.clone()),
+                (None, None) => return Err(Error::Parse("Posting has no amount".to_owned())),
+            };
+            Ok((
+                posting.account.as_str(),
+                amount,
+                posting.base_amount.clone(),
+            ))
+        })
+        .collect()
+}
+
+fn find_type(account: &str, category_mapping: &CategoryMapping) -> TransactionType {
+    // Entries may name the full account or leave out the top-level account
+    // (`Expenses:Auto:Fuel` or `Auto:Fuel`)
+    let without_root = account.split_once(':').map(|(_, rest)| rest);
+
+    category_mapping
+        .find_entry(account)
+        .or_else(|| without_root.and_then(|rest| category_mapping.find_entry(rest)))
+        .unwrap_or_else(|| category_mapping.find(account))
+}
+
+fn is_balance_account(account: &str) -> bool {
+    let root = account.split(':').next().unwrap_or_default();
+
+    BALANCE_ACCOUNTS
+        .iter()
+        .any(|a| a.eq_ignore_ascii_case(root))
+}
+
+fn is_metadata(line: &str) -> bool {
+    let first = line.split_whitespace().next().unwrap_or_default();
+
+    first.ends_with(':') && first.len() > 1 && first[..1].chars().all(|c| c.is_ascii_lowercase())
+}
+
+/// Parse a posting like `Expenses:Food  10.00 CHF @ 1.05 EUR`
+fn parse_posting(line: &str) -> Res<Posting> {
+    // Remove the cleared/pending marks
+    let line = line.trim_start_matches(['*', '!']).trim_start();
+
+    // Ledger separates the account from the amount by two spaces or a tab, beancount accounts
+    // contain no spaces
+    let (account, rest) = match line.find("  ").or_else(|| line.find('\t')) {
+        Some(position) => (&line[..position], line[position..].trim()),
+        None => match line.split_once(' ') {
+            Some((account, rest)) => (account, rest.trim()),
+            None => (line, ""),
+        },
+    };
+    let account = account.trim().to_owned();
+    if rest.is_empty() {
+        return Ok(Posting {
+            account,
+            amount: None,
+            base_amount: None,
+        });
+    }
+
+    // Costs in braces (`{1.05 EUR}`) and balance assertions (`= 100 EUR`) are not needed
+    let rest = rest.split(['{', '=']).next().unwrap_or_default().trim();
+    let (raw_amount, price) = match rest.split_once('@') {
+        Some((raw_amount, price)) => (raw_amount.trim(), Some(price)),
+        None => (rest, None),
+    };
+    let amount = parse_amount(raw_amount)?;
+
+    let base_amount = match price {
+        // `@@` gives the total price, `@` the price per unit
+        Some(price) => {
+            let (price, is_total) = match price.strip_prefix('@') {
+                Some(total) => (parse_amount(total.trim())?, true),
+                None => (parse_amount(price.trim())?, false),
+            };
+            let value = if is_total {
+                price.value.abs() * amount.value.signum()
+            } else {
+                price.value * amount.value
+            };
+
+            // Rates are only stored relative to the base currency
+            if price.currency == Currency::base() {
+                Some(Amount::new(value, price.currency))
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+
+    Ok(Posting {
+        account,
+        amount: Some(amount),
+        base_amount,
+    })
+}
+
+/// Parse an amount like `-10.00 CHF`, `$5`, `€ 1,234.50` or `EUR 3`
+fn parse_amount(raw_amount: &str) -> Res<Amount> {
+    let captures = AMOUNT
+        .captures(raw_amount)
+        .ok_or_else(|| Error::Parse(format!("Could not parse amount '{}'", raw_amount)))?;
+
+    let raw_value: String = captures[2]
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .collect();
+    let value = raw_value
+        .parse::<f64>()
+        .map_err(|e| Error::Parse(format!("Could not parse amount '{}': {}", raw_amount, e)))?;
+
+    let raw_currency = captures
+        .get(1)
+        .or_else(|| captures.get(3))
+        .map(|m| m.as_str().trim_matches('"'))
+        .ok_or_else(|| Error::Parse(format!("Amount '{}' has no currency", raw_amount)))?;
+
+    Ok(Amount::new(value, Currency::from_str(raw_currency)?))
+}
+
+fn parse_date(raw_date: &str) -> Res<NaiveDate> {
+    let normalized = raw_date.replace(['/', '.'], "-");
+
+    NaiveDate::parse_from_str(&normalized, "%Y-%m-%d")
+        .map_err(|e| Error::Parse(format!("Could not parse date '{}': {}", raw_date, e)))
+}
+
+/// Build the note from the text after the date
+///
+/// Beancount writes `* "payee" "narration"`, ledger `* (code) description`
+fn parse_description(rest: &str) -> Option<String> {
+    let rest = rest.trim_start_matches(['*', '!', ' ']).trim_start();
+    let rest = match rest.strip_prefix("txn") {
+        Some(rest) => rest.trim_start(),
+        None => rest,
+    };
+    let rest = match (rest.starts_with('('), rest.find(')')) {
+        (true, Some(end)) => rest[end + 1..].trim_start(),
+        _ => rest,
+    };
+
+    let description = if rest.starts_with('"') {
+        rest.split('"')
+            .skip(1)
+            .step_by(2)
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" - ")
+    } else {
+        // hledger separates payee and note with `|`
+        rest.split('|')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" - ")
+    };
+
+    if description.is_empty() {
+        None
+    } else {
+        Some(description)
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.trim_start().chars().next() {
+        // Whole-line comments
+        Some(';') | Some('#') | Some('%') | Some('|') => "",
+        _ => line.split(';').next().unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEDGER: &str = "; Household journal
+account Expenses:Food
+
+2024/05/01 * (42) Supermarket | weekly shopping
+    Expenses:Food:Groceries        45.90 EUR
+    Assets:Checking
+
+2024-05-03 Lunch in Zurich
+    Expenses:Dining  10.00 CHF @ 1.05 EUR  ; paid by card
+    Liabilities:CreditCard  -10.50 EUR
+
+2024-05-04 Gas station
+    Expenses:Auto:Fuel   $ 40.00
+    Expenses:Misc        $ 2.00
+    Assets:Cash
+
+2024-05-05 Broken
+    Expenses:Food  ten EUR
+    Assets:Cash
+";
+
+    const BEANCOUNT: &str = r#"option "operating_currency" "EUR"
+2024-01-01 open Assets:Checking
+2024-01-01 open Expenses:Health
+
+2024-06-01 * "Pharmacy" "Vitamins"
+  Expenses:Health 12.40 EUR
+    receipt: "scan.pdf"
+  Assets:Checking -12.40 EUR
+
+2024-06-02 * "Salary"
+  Income:Salary -2500.00 CHF @@ 2625.00 EUR
+  Assets:Checking
+"#;
+
+    #[test]
+    fn parse_ledger() {
+        let result = parse(
+            LEDGER,
+            &CategoryMapping::default(),
+            RowCollector::new(|t| Ok(Some(t)), None),
+        );
+
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].to_string().contains("line 18"));
+        assert_eq!(result.transactions.len(), 4);
+
+        let groceries = &result.transactions[0];
+        assert_eq!(groceries.date, NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+        assert_eq!(groceries.amount, Amount::new(-45.9, Currency::eur()));
+        assert_eq!(groceries.transaction_type, TransactionType::Eat);
+        assert_eq!(
+            groceries.note,
+            Some("Supermarket - weekly shopping".to_owned())
+        );
+
+        let lunch = &result.transactions[1];
+        assert_eq!(lunch.amount, Amount::new(-10.0, Currency::chf()));
+        assert_eq!(lunch.base_amount, Some(Amount::new(-10.5, Currency::eur())));
+        assert_eq!(lunch.transaction_type, TransactionType::Eat);
+
+        assert_eq!(
+            result.transactions[2].amount,
+            Amount::new(-40.0, Currency::usd())
+        );
+        assert_eq!(
+            result.transactions[2].transaction_type,
+            TransactionType::Gas
+        );
+        assert_eq!(
+            result.transactions[3].transaction_type,
+            TransactionType::Unknown
+        );
+    }
+
+    #[test]
+    fn parse_beancount() {
+        let result = parse(
+            BEANCOUNT,
+            &CategoryMapping::default(),
+            RowCollector::new(|t| Ok(Some(t)), None),
+        );
+
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.transactions.len(), 2);
+
+        let pharmacy = &result.transactions[0];
+        assert_eq!(pharmacy.amount, Amount::new(-12.4, Currency::eur()));
+        assert_eq!(pharmacy.transaction_type, TransactionType::Health);
+        assert_eq!(pharmacy.note, Some("Pharmacy - Vitamins".to_owned()));
+
+        let salary = &result.transactions[1];
+        assert_eq!(salary.amount, Amount::new(2500.0, Currency::chf()));
+        assert_eq!(
+            salary.base_amount,
+            Some(Amount::new(2625.0, Currency::eur()))
+        );
+    }
+
+    #[test]
+    fn parse_amounts() {
+        assert_eq!(
+            parse_amount("-1,234.50 CHF").unwrap(),
+            Amount::new(-1234.5, Currency::chf())
+        );
+        assert_eq!(
+            parse_amount("$5").unwrap(),
+            Amount::new(5.0, Currency::usd())
+        );
+        assert_eq!(
+            parse_amount("€ 3").unwrap(),
+            Amount::new(3.0, Currency::eur())
+        );
+        assert!(parse_amount("12").is_err());
+    }
+}
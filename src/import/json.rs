@@ -1,70 +1,372 @@
-use chrono::{DateTime, FixedOffset};
-
-use super::ImportResult;
+use super::{
+    diagnostic::{Diagnostic, SourceSpan},
+    ImportResult, RowCollector,
+};
 use crate::{
     currency::Currency,
     error::{Error, Res},
     transaction::{amount::Amount, transaction_type::TransactionType, Transaction},
 };
-use std::{fs::File, io::BufReader, path::Path, str::FromStr};
-
-#[derive(Debug, serde::Deserialize)]
-struct TransactionJson {
-    amount: f64,
-    date: DateTime<FixedOffset>,
-    currency: String,
-    note: String,
-}
-
-impl TransactionJson {
-    fn into_transaction<P>(self, mut prepare_transaction: P) -> Res<Option<Transaction>>
-    where
-        P: FnMut(Transaction) -> Res<Option<Transaction>>,
-    {
-        let currency = Currency::from_str(&self.currency)?;
-
-        prepare_transaction(Transaction::new(
-            self.date.date_naive(),
-            Amount::new(self.amount, currency),
-            None,
-            TransactionType::Unknown,
-            Some(self.note.trim().to_owned()),
-        ))
-    }
-}
+use chrono::{DateTime, NaiveDate};
+use serde_json::{Map, Value};
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
 
+/// Latest version of the JSON schema
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Import a JSON file
+///
+/// The file is either a plain array of transactions or a document of the form
+/// `{"version": 1, "transactions": [...]}`. A transaction has the fields
+///
+/// - `date` (required): `YYYY-MM-DD` or RFC 3339
+/// - `amount` (required): expenses are negative
+/// - `currency` (required): ISO code or symbol
+/// - `note`, `type` (identifier or name), `tags` (array of strings), `account`
+/// - `base_amount`: the actual amount in the base currency
+/// - `external_id`: reference of the source, used to skip transactions that are already imported
 pub fn get_transactions<T, P: AsRef<Path>>(
     input_file: P,
-    mut prepare_transaction: T,
+    rows: RowCollector<T>,
+) -> Result<ImportResult, Error>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let content = fs::read_to_string(input_file)?;
+    let document: Value = serde_json::from_str(&content).map_err(|e| {
+        let source_line = content.lines().nth(e.line().saturating_sub(1));
+        syntax_error(&e, source_line.unwrap_or_default())
+    })?;
+
+    parse(document, rows)
+}
+
+/// Import newline-delimited JSON, one transaction object per line
+///
+/// The file is read line by line, so large exports don't have to fit into memory
+pub fn get_ndjson_transactions<T, P: AsRef<Path>>(
+    input_file: P,
+    mut rows: RowCollector<T>,
 ) -> Result<ImportResult, Error>
 where
     T: FnMut(Transaction) -> Res<Option<Transaction>>,
 {
     let file = File::open(input_file)?;
-    let reader = BufReader::new(file);
 
-    let transaction_json: Vec<TransactionJson> = serde_json::from_reader(reader)?;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
 
-    let (transactions, errors) = partition_and_unpack(
-        transaction_json
-            .into_iter()
-            .map(|t| t.into_transaction(&mut prepare_transaction)),
+        let transaction = serde_json::from_str::<Value>(&line)
+            .map_err(|e| syntax_error(&e, &line))
+            .and_then(|value| read_transaction(&value));
+        rows.push_located(transaction, |e| locate_in_line(e, index + 1, &line));
+        if rows.is_full() {
+            break;
+        }
+    }
+
+    Ok(rows.finish())
+}
+
+pub(crate) fn parse<T>(document: Value, mut rows: RowCollector<T>) -> Result<ImportResult, Error>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let (items, parent_path) = match document {
+        Value::Array(items) => (items, "$"),
+        Value::Object(mut object) => {
+            match object.get("version").map(Value::as_u64) {
+                Some(Some(version)) if version <= SCHEMA_VERSION => {}
+                Some(Some(version)) => {
+                    return Err(Error::Import(format!(
+                        "Unsupported JSON schema version {}, the latest is {}",
+                        version, SCHEMA_VERSION
+                    )))
+                }
+                _ => {
+                    return Err(Error::Parse(
+                        "Field 'version': expected a positive integer".to_owned(),
+                    ))
+                }
+            }
+            match object.remove("transactions") {
+                Some(Value::Array(items)) => (items, "$.transactions"),
+                _ => {
+                    return Err(Error::Parse(
+                        "Field 'transactions': expected an array".to_owned(),
+                    ))
+                }
+            }
+        }
+        _ => {
+            return Err(Error::Parse(
+                "Expected an array of transactions or a document with a version".to_owned(),
+            ))
+        }
+    };
+
+    for (index, item) in items.iter().enumerate() {
+        rows.push_located(read_transaction(item), |e| {
+            locate(e, &format!("{}[{}]", parent_path, index)).into()
+        });
+        if rows.is_full() {
+            break;
+        }
+    }
+
+    Ok(rows.finish())
+}
+
+fn read_transaction(value: &Value) -> Res<Transaction> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| Error::Parse("Expected an object".to_owned()))?;
+
+    let date = parse_date(&required_string(object, "date")?)
+        .map_err(|message| field_error("date", message))?;
+    let currency = Currency::from_str(&required_string(object, "currency")?)
+        .map_err(|e| field_error("currency", e.message()))?;
+    let amount = required_number(object, "amount")?;
+    let transaction_type = match optional_string(object, "type")? {
+        Some(raw_type) => parse_type(&raw_type).map_err(|message| field_error("type", message))?,
+        None => TransactionType::Unknown,
+    };
+    let base_amount =
+        optional_number(object, "base_amount")?.map(|value| Amount::new(value, Currency::base()));
+
+    let mut transaction = Transaction::new(
+        date,
+        Amount::new(amount, currency),
+        base_amount,
+        transaction_type,
+        optional_string(object, "note")?.map(|note| note.trim().to_owned()),
     );
+    transaction.external_id = optional_string(object, "external_id")?;
+    transaction.tags = optional_strings(object, "tags")?;
+    // There are no accounts yet, so the account is kept as a tag
+    if let Some(account) = optional_string(object, "account")?.filter(|a| !a.is_empty()) {
+        transaction.tags.push(format!("account:{}", account));
+    }
 
-    Ok(ImportResult {
-        transactions,
-        errors,
-    })
+    Ok(transaction)
 }
 
-fn partition_and_unpack<T: std::fmt::Debug, E: std::fmt::Debug>(
-    input: impl Iterator<Item = Result<Option<T>, E>>,
-) -> (Vec<T>, Vec<E>) {
-    let (oks, errors): (Vec<Result<Option<T>, _>>, Vec<Result<_, E>>) =
-        input.into_iter().partition(Result::is_ok);
+/// Prefix the JSON path of the error with the path of the transaction
+fn locate(error: Error, parent_path: &str) -> Diagnostic {
+    let mut diagnostic = Diagnostic::from(error);
+    diagnostic.span.json_path = Some(format!(
+        "{}{}",
+        parent_path,
+        diagnostic.span.json_path.unwrap_or_default()
+    ));
+
+    diagnostic
+}
+
+/// Place the error of an NDJSON line, a failed field is underlined where its key is written
+fn locate_in_line(error: Error, line_number: usize, line: &str) -> Error {
+    let mut diagnostic = Diagnostic::from(error);
+    if diagnostic.span.line.is_none() {
+        let field = diagnostic
+            .span
+            .json_path
+            .as_deref()
+            .and_then(|path| path.strip_prefix('.'))
+            .map(|field| format!("\"{}\"", field));
+        let mut span = SourceSpan::line(line_number);
+        if let Some(position) = field.as_ref().and_then(|field| line.find(field.as_str())) {
+            let column = line[..position].chars().count() + 1;
+            span = span.with_column(column, field.unwrap_or_default().chars().count());
+        }
+        span.json_path = diagnostic.span.json_path.map(|path| format!("${}", path));
+        diagnostic.span = span;
+    } else {
+        diagnostic.span.line = Some(line_number);
+    }
+
+    diagnostic.with_source_line(line).into()
+}
+
+fn syntax_error(error: &serde_json::Error, source_line: &str) -> Error {
+    // The position is shown by the span, not in the message
+    let message = error.to_string();
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message.as_str(), |(message, _)| message);
+
+    Diagnostic::new(
+        format!("Invalid JSON: {}", message),
+        SourceSpan::line(error.line()).with_column(error.column(), 1),
+    )
+    .with_source_line(source_line)
+    .into()
+}
 
-    (
-        oks.into_iter().filter_map(Result::unwrap).collect(),
-        errors.into_iter().map(Result::unwrap_err).collect(),
+fn field_error<S: AsRef<str>>(field: &str, message: S) -> Error {
+    Diagnostic::new(
+        format!("Field '{}': {}", field, message.as_ref()),
+        SourceSpan::json_path(format!(".{}", field)),
     )
+    .into()
+}
+
+fn required_string(object: &Map<String, Value>, field: &str) -> Res<String> {
+    optional_string(object, field)?.ok_or_else(|| field_error(field, "is missing"))
+}
+
+fn optional_string(object: &Map<String, Value>, field: &str) -> Res<Option<String>> {
+    match object.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(field_error(field, "expected a string")),
+    }
+}
+
+fn required_number(object: &Map<String, Value>, field: &str) -> Res<f64> {
+    optional_number(object, field)?.ok_or_else(|| field_error(field, "is missing"))
+}
+
+fn optional_number(object: &Map<String, Value>, field: &str) -> Res<Option<f64>> {
+    match object.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Number(n)) => Ok(n.as_f64()),
+        Some(_) => Err(field_error(field, "expected a number")),
+    }
+}
+
+fn optional_strings(object: &Map<String, Value>, field: &str) -> Res<Vec<String>> {
+    match object.get(field) {
+        None | Some(Value::Null) => Ok(vec![]),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s.clone()),
+                _ => Err(field_error(field, "expected an array of strings")),
+            })
+            .collect(),
+        Some(_) => Err(field_error(field, "expected an array of strings")),
+    }
+}
+
+fn parse_date(raw_date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(raw_date, "%Y-%m-%d")
+        .or_else(|_| DateTime::parse_from_rfc3339(raw_date).map(|d| d.date_naive()))
+        .map_err(|_| format!("'{}' is neither YYYY-MM-DD nor RFC 3339", raw_date))
+}
+
+fn parse_type(raw_type: &str) -> Result<TransactionType, String> {
+    match TransactionType::from_str(raw_type) {
+        TransactionType::Unknown
+            if !raw_type.is_empty()
+                && !raw_type.eq_ignore_ascii_case("U")
+                && !raw_type.eq_ignore_ascii_case(TransactionType::Unknown.name()) =>
+        {
+            Err(format!("Unknown type '{}'", raw_type))
+        }
+        transaction_type => Ok(transaction_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(content: &str) -> Res<ImportResult> {
+        parse(
+            serde_json::from_str(content).unwrap(),
+            RowCollector::new(|t| Ok(Some(t)), None),
+        )
+    }
+
+    #[test]
+    fn parse_legacy_array() {
+        let result = parse_str(
+            r#"[{"amount": -12.5, "date": "2024-03-01T10:00:00+01:00", "currency": "EUR", "note": " Migros "}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(result.transactions.len(), 1);
+        let transaction = &result.transactions[0];
+        assert_eq!(
+            transaction.date,
+            NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+        );
+        assert_eq!(transaction.amount, Amount::new(-12.5, Currency::eur()));
+        assert_eq!(transaction.note.as_deref(), Some("Migros"));
+        assert_eq!(transaction.transaction_type, TransactionType::Unknown);
+    }
+
+    #[test]
+    fn parse_versioned_document() {
+        let result = parse_str(
+            r#"{
+                "version": 1,
+                "transactions": [
+                    {
+                        "date": "2024-03-02", "amount": -30, "currency": "CHF",
+                        "type": "Food", "tags": ["weekly"], "account": "Card",
+                        "base_amount": -31.2, "external_id": "TX-1"
+                    },
+                    {"date": "2024-03-03", "amount": "ten", "currency": "EUR"},
+                    {"date": "03.03.2024", "amount": 1, "currency": "EUR"},
+                    {"date": "2024-03-04", "amount": 1, "currency": "EUR", "type": "Spaceship"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(result.transactions.len(), 1);
+        let transaction = &result.transactions[0];
+        assert_eq!(transaction.transaction_type, TransactionType::Eat);
+        assert_eq!(transaction.tags, vec!["weekly", "account:Card"]);
+        assert_eq!(
+            transaction.base_amount,
+            Some(Amount::new(-31.2, Currency::base()))
+        );
+        assert_eq!(transaction.external_id.as_deref(), Some("TX-1"));
+
+        let locations: Vec<String> = result
+            .errors
+            .iter()
+            .map(|e| format!("{} {}", Diagnostic::from(e.clone()).span, e.message()))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                "$.transactions[1].amount Field 'amount': expected a number",
+                "$.transactions[2].date Field 'date': '03.03.2024' is neither YYYY-MM-DD nor RFC 3339",
+                "$.transactions[3].type Field 'type': Unknown type 'Spaceship'",
+            ]
+        );
+    }
+
+    #[test]
+    fn locate_ndjson_errors() {
+        let line = r#"{"date": "2024-03-02", "amount": "ten", "currency": "EUR"}"#;
+        let error = read_transaction(&serde_json::from_str(line).unwrap()).unwrap_err();
+        let diagnostic = Diagnostic::from(locate_in_line(error, 7, line));
+
+        assert_eq!(diagnostic.span.to_string(), "7:24 $.amount");
+        assert_eq!(diagnostic.span.length, 8);
+        assert_eq!(diagnostic.source_line.as_deref(), Some(line));
+
+        let error = serde_json::from_str::<Value>(r#"{"date": }"#).unwrap_err();
+        let diagnostic = Diagnostic::from(syntax_error(&error, r#"{"date": }"#));
+        assert_eq!(diagnostic.message, "Invalid JSON: expected value");
+        assert_eq!(diagnostic.span, SourceSpan::line(1).with_column(10, 1));
+    }
+
+    #[test]
+    fn reject_newer_versions() {
+        assert!(parse_str(r#"{"version": 2, "transactions": []}"#).is_err());
+        assert!(parse_str(r#"{"transactions": []}"#).is_err());
+    }
 }
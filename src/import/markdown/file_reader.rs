@@ -1,4 +1,5 @@
 use crate::error::Error;
+use crate::import::diagnostic::{Diagnostic, SourceSpan};
 use std::fs::File;
 use std::io;
 use std::io::{BufRead, BufReader};
@@ -6,9 +7,88 @@ use std::path::Path;
 
 pub type LineParts = Vec<String>;
 
-#[allow(dead_code)]
+/// A table row with its position in the file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub line_number: usize,
+    /// The line as written in the file
+    pub text: String,
+    pub parts: LineParts,
+}
+
+impl Row {
+    /// Span of the cell at `index`, or of the whole line if there is no such cell
+    pub fn span(&self, index: Option<usize>) -> SourceSpan {
+        let span = SourceSpan::line(self.line_number);
+        match index.and_then(|index| split_cells(&self.text).into_iter().nth(index)) {
+            Some(cell) => span.with_column(cell.column, cell.length),
+            None => span,
+        }
+    }
+}
+
+/// A cell of a table line
+#[derive(Debug, PartialEq)]
+struct Cell {
+    /// 1-based column of the trimmed text, counted in characters
+    column: usize,
+    /// Length of the trimmed text as written, counted in characters
+    length: usize,
+    /// Trimmed text with escaped pipes (`\|`) and line breaks (`<br />`) replaced
+    text: String,
+}
+
+/// Split a table line into cells, `\|` is a pipe inside a cell
+fn split_cells(line: &str) -> Vec<Cell> {
+    let chars: Vec<char> = line.chars().collect();
+    let is_separator =
+        |index: usize| chars[index] == '|' && (index == 0 || chars[index - 1] != '\\');
+
+    let mut start = chars.iter().take_while(|c| c.is_whitespace()).count();
+    while start < chars.len() && chars[start] == '|' {
+        start += 1;
+    }
+    let mut end = chars.len() - chars.iter().rev().take_while(|c| c.is_whitespace()).count();
+    while end > start && is_separator(end - 1) {
+        end -= 1;
+    }
+
+    let mut cells = vec![];
+    let mut cell_start = start;
+    for index in start..=end {
+        if index == end || is_separator(index) {
+            let cell = &chars[cell_start..index];
+            let leading = cell.iter().take_while(|c| c.is_whitespace()).count();
+            let trailing = cell[leading..]
+                .iter()
+                .rev()
+                .take_while(|c| c.is_whitespace())
+                .count();
+            let text: String = cell[leading..cell.len() - trailing].iter().collect();
+            cells.push(Cell {
+                column: cell_start + leading + 1,
+                length: cell.len() - leading - trailing,
+                text: text.replace("\\|", "|").replace("<br />", "\n"),
+            });
+            cell_start = index + 1;
+        }
+    }
+
+    cells
+}
+
+/// A Markdown table with its header row
+#[derive(Debug, PartialEq)]
+pub struct Table {
+    /// Text of the last heading before the table
+    pub heading: Option<String>,
+    pub header: Row,
+    pub rows: Vec<Row>,
+}
+
 pub struct ReadFileResult {
-    pub lines: Vec<LineParts>,
+    pub tables: Vec<Table>,
+    /// Lines that could not be read, located by their line number
     pub errors: Vec<Error>,
 }
 
@@ -23,27 +103,53 @@ impl FileReader {
             )));
         }
         let f = File::open(input_file)?;
-        let file = BufReader::new(&f);
-        let mut lines = vec![];
+
+        Ok(FileReader::read_tables(BufReader::new(&f).lines()))
+    }
+
+    /// Split the lines into tables
+    ///
+    /// A row followed by a separator line is the header of a new table. Files without separator
+    /// lines use the first row of each section as header
+    pub(crate) fn read_tables<I: Iterator<Item = Result<String, io::Error>>>(
+        lines: I,
+    ) -> ReadFileResult {
+        let mut reader = TableReader::default();
         let mut errors = vec![];
 
-        let mut number_of_relevant_lines = 0;
-        for line in file.lines() {
-            let result = FileReader::read_line(line);
-
-            match result {
-                Ok(i) => {
-                    // The first match will be the header, collect only the remaining ones
-                    if number_of_relevant_lines != 0 {
-                        lines.push(i);
-                    }
-                    number_of_relevant_lines += 1;
+        for (index, line) in lines.enumerate() {
+            if let Ok(heading) = &line {
+                if let Some(heading) = heading.trim().strip_prefix('#') {
+                    reader.start_section(heading.trim_start_matches('#').trim());
+                    continue;
+                }
+                if !heading.contains('|') {
+                    // Paragraphs between the tables
+                    continue;
                 }
-                Err(e) => errors.push(e),
+            }
+
+            let text = line.as_ref().map(String::clone).unwrap_or_default();
+            match FileReader::read_line(line) {
+                Ok(parts) => reader.add_row(Row {
+                    line_number: index + 1,
+                    text,
+                    parts,
+                }),
+                Err(Error::LineSeparator) => reader.add_separator(),
+                Err(Error::LineEmpty) | Err(Error::LineComment) => (),
+                Err(e) => errors.push(
+                    Diagnostic::new(e.message(), SourceSpan::line(index + 1))
+                        .with_source_line(text)
+                        .into(),
+                ),
             }
         }
 
-        Ok(ReadFileResult { lines, errors })
+        ReadFileResult {
+            tables: reader.finish(),
+            errors,
+        }
     }
 
     fn read_line(line: Result<String, io::Error>) -> Result<LineParts, Error> {
@@ -57,17 +163,17 @@ impl FileReader {
                 } else if trimmed.is_empty() {
                     Err(Error::LineEmpty)
                 } else {
-                    FileReader::check_line_parts(trimmed)
+                    FileReader::check_line_parts(l)
                 }
             }
             Err(ref e) => Err(Error::from(e)),
         }
     }
 
-    fn check_line_parts(trimmed: String) -> Result<LineParts, Error> {
-        let parts = trimmed
-            .split('|')
-            .map(|part| part.trim().to_owned())
+    fn check_line_parts(line: &str) -> Result<LineParts, Error> {
+        let parts = split_cells(line)
+            .into_iter()
+            .map(|cell| cell.text)
             .collect::<LineParts>();
 
         if parts.iter().any(|part| part.starts_with("--")) {
@@ -78,6 +184,62 @@ impl FileReader {
     }
 }
 
+#[derive(Default)]
+struct TableReader {
+    tables: Vec<Table>,
+    heading: Option<String>,
+    in_table: bool,
+    /// The last row is kept back, it could be the header of the next table
+    pending_row: Option<Row>,
+}
+
+impl TableReader {
+    fn start_section(&mut self, heading: &str) {
+        self.flush();
+        self.heading = Some(heading.to_owned()).filter(|h| !h.is_empty());
+        self.in_table = false;
+    }
+
+    fn add_row(&mut self, row: Row) {
+        self.flush();
+        self.pending_row = Some(row);
+    }
+
+    fn add_separator(&mut self) {
+        if let Some(header) = self.pending_row.take() {
+            self.tables.push(Table {
+                heading: self.heading.clone(),
+                header,
+                rows: vec![],
+            });
+            self.in_table = true;
+        }
+    }
+
+    fn flush(&mut self) {
+        let Some(row) = self.pending_row.take() else {
+            return;
+        };
+
+        match self.tables.last_mut() {
+            Some(table) if self.in_table => table.rows.push(row),
+            _ => {
+                self.tables.push(Table {
+                    heading: self.heading.clone(),
+                    header: row,
+                    rows: vec![],
+                });
+                self.in_table = true;
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<Table> {
+        self.flush();
+        self.tables
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,4 +327,114 @@ mod tests {
             Err(Error::LineComment)
         );
     }
+
+    fn parts(values: &[&str]) -> LineParts {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn numbered_parts(rows: &[Row]) -> Vec<(usize, LineParts)> {
+        rows.iter()
+            .map(|row| (row.line_number, row.parts.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_read_tables() {
+        let content = "# Januar\n\
+            | Datum | Betrag |\n\
+            | ----- | ------ |\n\
+            | 01.01.2024 | 10 |\n\
+            \n\
+            <!-- | 02.01.2024 | 20 | -->\n\
+            | 03.01.2024 | 30 |\n\
+            \n\
+            Some text\n\
+            ## Februar\n\
+            | Date | Amount |\n\
+            |---|---|\n\
+            | 01.02.2024 | 40 |\n\
+            \n\
+            | Date | Amount |\n\
+            |---|---|\n\
+            | 02.02.2024 | 50 |\n";
+
+        let result = FileReader::read_tables(content.lines().map(|l| Ok(l.to_owned())));
+        assert_eq!(result.tables.len(), 3);
+        assert_eq!(result.tables[0].heading.as_deref(), Some("Januar"));
+        assert_eq!(result.tables[0].header.parts, parts(&["Datum", "Betrag"]));
+        assert_eq!(
+            numbered_parts(&result.tables[0].rows),
+            vec![
+                (4, parts(&["01.01.2024", "10"])),
+                (7, parts(&["03.01.2024", "30"]))
+            ]
+        );
+        assert_eq!(result.tables[1].heading.as_deref(), Some("Februar"));
+        assert_eq!(
+            numbered_parts(&result.tables[1].rows),
+            vec![(13, parts(&["01.02.2024", "40"]))]
+        );
+        assert_eq!(result.tables[2].heading.as_deref(), Some("Februar"));
+        assert_eq!(
+            numbered_parts(&result.tables[2].rows),
+            vec![(17, parts(&["02.02.2024", "50"]))]
+        );
+    }
+
+    #[test]
+    fn test_read_tables_without_separator() {
+        let content = "| Datum | Betrag |\n| 01.01.2024 | 10 |\n| 02.01.2024 | 20 |";
+
+        let result = FileReader::read_tables(content.lines().map(|l| Ok(l.to_owned())));
+        assert_eq!(result.tables.len(), 1);
+        assert_eq!(result.tables[0].rows.len(), 2);
+    }
+
+    #[test]
+    fn test_read_tables_reports_unreadable_lines() {
+        let lines = vec![
+            Ok("| Datum | Betrag |".to_owned()),
+            Ok(String::new()),
+            Err(io::Error::new(io::ErrorKind::InvalidData, "invalid UTF-8")),
+            Ok("| 01.01.2024 | 10 |".to_owned()),
+        ];
+
+        let result = FileReader::read_tables(lines.into_iter());
+        assert_eq!(result.tables.len(), 1);
+        assert_eq!(result.errors.len(), 1);
+        match &result.errors[0] {
+            Error::Diagnostic(d) => assert_eq!(d.span, SourceSpan::line(3)),
+            e => panic!("Expected a diagnostic, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_row_span() {
+        let row = Row {
+            line_number: 4,
+            text: "  | 16.01.2024 |  € | abc |".to_owned(),
+            parts: parts(&["16.01.2024", "€", "abc"]),
+        };
+
+        assert_eq!(row.span(Some(0)), SourceSpan::line(4).with_column(5, 10));
+        assert_eq!(row.span(Some(1)), SourceSpan::line(4).with_column(19, 1));
+        assert_eq!(row.span(Some(2)), SourceSpan::line(4).with_column(23, 3));
+        assert_eq!(row.span(Some(3)), SourceSpan::line(4));
+        assert_eq!(
+            split_cells("a|b")
+                .iter()
+                .map(|cell| (cell.column, cell.length))
+                .collect::<Vec<_>>(),
+            vec![(1, 1), (3, 1)],
+            "Rows without outer pipes"
+        );
+    }
+
+    #[test]
+    fn test_read_line_escaped_pipe() {
+        assert_eq!(
+            FileReader::read_line(Ok(r"| 01.01.2024 | Rock \| Pop \| |".to_string())),
+            Ok(vec!["01.01.2024".to_string(), "Rock | Pop |".to_string()])
+        );
+    }
 }
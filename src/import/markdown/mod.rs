@@ -3,12 +3,21 @@ mod transaction_parser;
 
 pub use self::file_reader::FileReader;
 pub use self::transaction_parser::TransactionParser;
-use super::ImportResult;
-use crate::error::Error;
+use super::{ImportResult, RowCollector};
+use crate::{error::Error, transaction::Transaction};
 use std::path::Path;
 
-pub fn get_transactions<P: AsRef<Path>>(input_file: P) -> Result<ImportResult, Error> {
-    let lines = FileReader::read(input_file)?;
+pub fn get_transactions<T, P: AsRef<Path>>(
+    input_file: P,
+    mut rows: RowCollector<T>,
+) -> Result<ImportResult, Error>
+where
+    T: FnMut(Transaction) -> Result<Option<Transaction>, Error>,
+{
+    let result = FileReader::read(input_file)?;
+    for error in result.errors {
+        rows.push(Err(error));
+    }
     let parser = TransactionParser::new();
-    Ok(parser.parse_lines(lines.lines))
+    Ok(parser.parse_tables(result.tables, rows))
 }
@@ -1,14 +1,118 @@
 use crate::currency::Currency;
 use crate::error::Error;
-use crate::import::markdown::file_reader::LineParts;
-use crate::import::ImportResult;
-use crate::transaction::amount::Amount;
+use crate::import::diagnostic::Diagnostic;
+use crate::import::markdown::file_reader::Table;
+use crate::import::{ImportResult, RowCollector};
+use crate::transaction::amount::{Amount, EnteredAmount};
 use crate::transaction::transaction_type::TransactionType;
-use crate::transaction::Transaction;
+use crate::transaction::{split_tags, Transaction};
 use chrono::NaiveDate;
 use std::cmp::Ordering;
 use std::str::FromStr;
 
+/// Position of the known columns in a table
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnMapping {
+    date: usize,
+    /// Without currency column, amounts are in the base currency
+    currency: Option<usize>,
+    amount: usize,
+    transaction_type: Option<usize>,
+    note: Option<usize>,
+    tags: Option<usize>,
+    account: Option<usize>,
+    sign: Option<usize>,
+    base_amount: Option<usize>,
+    external_id: Option<usize>,
+}
+
+const DATE_NAMES: [&str; 2] = ["datum", "date"];
+const CURRENCY_NAMES: [&str; 3] = ["währung", "waehrung", "currency"];
+const AMOUNT_NAMES: [&str; 2] = ["betrag", "amount"];
+const TYPE_NAMES: [&str; 4] = ["typ", "type", "kategorie", "category"];
+const NOTE_NAMES: [&str; 6] = [
+    "kommentar",
+    "notiz",
+    "beschreibung",
+    "note",
+    "comment",
+    "description",
+];
+const TAGS_NAMES: [&str; 2] = ["tags", "schlagwörter"];
+const ACCOUNT_NAMES: [&str; 2] = ["konto", "account"];
+const SIGN_NAMES: [&str; 3] = ["vorzeichen", "richtung", "sign"];
+const BASE_AMOUNT_NAMES: [&str; 3] = ["basisbetrag", "base amount", "base_amount"];
+const EXTERNAL_ID_NAMES: [&str; 3] = ["referenz", "external id", "external_id"];
+
+impl Default for ColumnMapping {
+    /// Fixed order of the original table layout
+    fn default() -> Self {
+        Self {
+            date: 0,
+            currency: Some(1),
+            amount: 2,
+            transaction_type: Some(3),
+            note: Some(4),
+            tags: None,
+            account: None,
+            sign: None,
+            base_amount: None,
+            external_id: None,
+        }
+    }
+}
+
+impl ColumnMapping {
+    /// Map the columns by the German or English names in the header row
+    ///
+    /// Tables whose header contains none of the known names use the fixed order
+    pub fn from_header(header: &[String]) -> Result<Self, Error> {
+        let names: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+        let find = |aliases: &[&str]| {
+            names
+                .iter()
+                .position(|name| aliases.contains(&name.as_str()))
+        };
+        let required = |aliases: &[&str]| {
+            find(aliases).ok_or_else(|| {
+                Error::Parse(format!(
+                    "Missing column '{}' in the header row",
+                    aliases.join("/")
+                ))
+            })
+        };
+
+        let all_names = [
+            &DATE_NAMES[..],
+            &CURRENCY_NAMES,
+            &AMOUNT_NAMES,
+            &TYPE_NAMES,
+            &NOTE_NAMES,
+            &TAGS_NAMES,
+            &ACCOUNT_NAMES,
+            &SIGN_NAMES,
+            &BASE_AMOUNT_NAMES,
+            &EXTERNAL_ID_NAMES,
+        ];
+        if all_names.iter().all(|aliases| find(aliases).is_none()) {
+            return Ok(Self::default());
+        }
+
+        Ok(Self {
+            date: required(&DATE_NAMES)?,
+            currency: find(&CURRENCY_NAMES),
+            amount: required(&AMOUNT_NAMES)?,
+            transaction_type: find(&TYPE_NAMES),
+            note: find(&NOTE_NAMES),
+            tags: find(&TAGS_NAMES),
+            account: find(&ACCOUNT_NAMES),
+            sign: find(&SIGN_NAMES),
+            base_amount: find(&BASE_AMOUNT_NAMES),
+            external_id: find(&EXTERNAL_ID_NAMES),
+        })
+    }
+}
+
 pub struct TransactionParser {}
 
 impl TransactionParser {
@@ -16,16 +120,53 @@ impl TransactionParser {
         TransactionParser {}
     }
 
-    pub fn parse_lines(&self, lines: Vec<LineParts>) -> ImportResult {
-        let mut transactions = vec![];
-        let mut errors = vec![];
-        for parts in lines {
-            match self.build_from_vec(parts.iter().map(String::as_str).collect()) {
-                Ok(transaction) => transactions.push(transaction),
-                Err(error) => errors.push(error),
+    /// Parse the rows of all tables, the transactions are sorted by date
+    pub fn parse_tables<T>(&self, tables: Vec<Table>, mut rows: RowCollector<T>) -> ImportResult
+    where
+        T: FnMut(Transaction) -> Result<Option<Transaction>, Error>,
+    {
+        for table in tables {
+            if rows.is_full() {
+                break;
+            }
+            let mapping = match ColumnMapping::from_header(&table.header.parts) {
+                Ok(mapping) => mapping,
+                Err(e) => {
+                    let message = format!(
+                        "Table {}: {}",
+                        table
+                            .heading
+                            .map_or_else(|| "without heading".to_owned(), |h| format!("'{}'", h)),
+                        e.message()
+                    );
+                    rows.push(Err(Diagnostic::new(message, table.header.span(None))
+                        .with_source_line(table.header.text)
+                        .into()));
+                    continue;
+                }
+            };
+
+            for row in table.rows {
+                if rows.is_full() {
+                    break;
+                }
+                match self.build_from_vec(&mapping, &row.parts) {
+                    Ok(transaction) => rows.push_located(Ok(transaction), |e| {
+                        Diagnostic::new(e.message(), row.span(None))
+                            .with_source_line(row.text)
+                            .into()
+                    }),
+                    Err((index, e)) => {
+                        rows.push(Err(Diagnostic::new(e.message(), row.span(Some(index)))
+                            .with_source_line(row.text)
+                            .into()))
+                    }
+                }
             }
         }
-        transactions.sort_by(|a, b| {
+
+        let mut result = rows.finish();
+        result.transactions.sort_by(|a, b| {
             if a.date() > b.date() {
                 Ordering::Greater
             } else if a.date() < b.date() {
@@ -37,37 +178,91 @@ impl TransactionParser {
             }
         });
 
-        ImportResult {
-            transactions,
-            errors,
-        }
+        result
     }
 
-    pub fn build_from_vec(&self, parts: Vec<&str>) -> Result<Transaction, Error> {
-        let string_vec: Vec<String> = parts.into_iter().map(String::from).collect();
+    /// Build the transaction of a row, errors come with the index of the offending cell
+    pub fn build_from_vec(
+        &self,
+        mapping: &ColumnMapping,
+        string_vec: &[String],
+    ) -> Result<Transaction, (usize, Error)> {
+        let date = self
+            .parse_date(string_vec, mapping.date)
+            .map_err(|e| (mapping.date, e))?;
+        let currency = match mapping.currency {
+            Some(index) => self
+                .get_vec_part_or_error(string_vec, index, "Could not read currency from line")
+                .and_then(|raw_currency| Currency::from_str(&raw_currency))
+                .map_err(|e| (index, e))?,
+            None => Currency::base(),
+        };
+        let mut entered_amount = self
+            .parse_amount(string_vec, mapping.amount)
+            .map_err(|e| (mapping.amount, e))?;
+        if let Some(index) = mapping.sign {
+            if let Some(sign) = self.get_optional_part(string_vec, Some(index)) {
+                entered_amount.is_income = parse_sign(&sign)
+                    .map_err(|e| (index, e))?
+                    .or(entered_amount.is_income);
+            }
+        }
 
-        let date = self.parse_date(&string_vec)?;
-        let raw_currency =
-            self.get_vec_part_or_error(&string_vec, 1, "Could not read currency from line")?;
-        let currency = Currency::from_str(&raw_currency)?;
-        let amount = Amount::new(-1.0 * self.parse_amount(&string_vec)?, currency);
+        let transaction_type = TransactionType::from_str(
+            &self
+                .get_optional_part(string_vec, mapping.transaction_type)
+                .unwrap_or_default(),
+        );
+        // Amounts are written as positive numbers, expenses are stored as negative ones
+        let amount = Amount::new(entered_amount.signed_value(transaction_type), currency);
+        let note = mapping
+            .note
+            .and_then(|index| self.get_vec_part(string_vec, index))
+            .filter(|note| !note.is_empty());
+        // The base amount is written without sign, like the amount, and gets the sign of the amount
+        let base_amount = match mapping.base_amount {
+            Some(index) => self
+                .get_optional_part(string_vec, Some(index))
+                .filter(|raw_base_amount| !raw_base_amount.is_empty())
+                .map(|raw_base_amount| {
+                    EnteredAmount::parse(&raw_base_amount).map_err(|e| {
+                        Error::Parse(format!(
+                            "Could not parse base amount '{}': {}",
+                            raw_base_amount, e
+                        ))
+                    })
+                })
+                .transpose()
+                .map_err(|e| (index, e))?
+                .map(|base_amount| {
+                    Amount::new(
+                        base_amount.value.abs().copysign(amount.value),
+                        Currency::base(),
+                    )
+                }),
+            None => None,
+        };
 
-        let transaction_type =
-            TransactionType::from_str(string_vec.get(3).unwrap_or(&"".to_string()));
-        let note = self.get_vec_part(&string_vec, 4);
-        let base_amount = None;
+        let mut transaction = Transaction::new(date, amount, base_amount, transaction_type, note);
+        transaction.external_id = self
+            .get_optional_part(string_vec, mapping.external_id)
+            .filter(|external_id| !external_id.is_empty());
+        if let Some(tags) = self.get_optional_part(string_vec, mapping.tags) {
+            transaction.tags = split_tags(&tags);
+        }
+        // There are no accounts yet, so the account is kept as a tag
+        if let Some(account) = self
+            .get_optional_part(string_vec, mapping.account)
+            .filter(|a| !a.is_empty())
+        {
+            transaction.tags.push(format!("account:{}", account));
+        }
 
-        Ok(Transaction::new(
-            date,
-            amount,
-            base_amount,
-            transaction_type,
-            note,
-        ))
+        Ok(transaction)
     }
 
-    fn parse_date(&self, string_vec: &[String]) -> Result<NaiveDate, Error> {
-        match self.get_vec_part_or_error(string_vec, 0, "Could not read date from line") {
+    fn parse_date(&self, string_vec: &[String], index: usize) -> Result<NaiveDate, Error> {
+        match self.get_vec_part_or_error(string_vec, index, "Could not read date from line") {
             Ok(s) => match NaiveDate::parse_from_str(&s, "%d.%m.%Y") {
                 Ok(d) => Ok(d),
                 Err(e) => Err(Error::Parse(format!(
@@ -79,16 +274,12 @@ impl TransactionParser {
         }
     }
 
-    fn parse_amount(&self, string_vec: &[String]) -> Result<f64, Error> {
+    fn parse_amount(&self, string_vec: &[String], index: usize) -> Result<EnteredAmount, Error> {
         let amount_string =
-            self.get_vec_part_or_error(string_vec, 2, "Could not read amount from line")?;
+            self.get_vec_part_or_error(string_vec, index, "Could not read amount from line")?;
 
-        match amount_string
-            .trim()
-            .replace(',', ".") // Replace ',' with '.'
-            .parse::<f64>()
-        {
-            Ok(f) => Ok(f),
+        match EnteredAmount::parse(&amount_string) {
+            Ok(entered_amount) => Ok(entered_amount),
             Err(e) => Err(Error::Parse(format!(
                 "Could not parse amount '{}': {}",
                 amount_string, e
@@ -100,6 +291,12 @@ impl TransactionParser {
         string_vec.get(index).map(|s| s.to_owned())
     }
 
+    fn get_optional_part(&self, string_vec: &[String], index: Option<usize>) -> Option<String> {
+        index
+            .and_then(|index| string_vec.get(index))
+            .map(|s| s.trim().to_owned())
+    }
+
     fn get_vec_part_or_error(
         &self,
         string_vec: &[String],
@@ -113,15 +310,29 @@ impl TransactionParser {
     }
 }
 
+/// Return whether the value of a sign column marks income, `None` for an empty cell
+fn parse_sign(sign: &str) -> Result<Option<bool>, Error> {
+    match sign.to_lowercase().as_str() {
+        "" => Ok(None),
+        "+" | "income" | "in" | "einnahme" => Ok(Some(true)),
+        "-" | "expense" | "out" | "ausgabe" => Ok(Some(false)),
+        _ => Err(Error::Parse(format!("Could not parse sign '{}'", sign))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::import::diagnostic::SourceSpan;
+    use crate::import::markdown::file_reader::Row;
 
     #[test]
     fn build_from_vec() {
         let transaction_parser = TransactionParser::new();
-        let result =
-            transaction_parser.build_from_vec(vec!["15.02.2019", "€", "66.60", "T", "Gas station"]);
+        let result = transaction_parser.build_from_vec(
+            &ColumnMapping::default(),
+            &header(&["15.02.2019", "€", "66.60", "T", "Gas station"]),
+        );
         match result {
             Ok(i) => {
                 assert_eq!(i.transaction_type(), TransactionType::Gas);
@@ -130,7 +341,117 @@ mod tests {
                 assert!(i.note().is_some());
                 assert_eq!(i.note().unwrap(), "Gas station");
             }
-            Err(e) => panic!("{}", e),
+            Err((_, e)) => panic!("{}", e),
         };
     }
+
+    fn header(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn row(line_number: usize, cells: &[&str]) -> Row {
+        Row {
+            line_number,
+            text: format!("| {} |", cells.join(" | ")),
+            parts: header(cells),
+        }
+    }
+
+    #[test]
+    fn build_from_header() {
+        let mapping = ColumnMapping::from_header(&header(&[
+            "Note", "Amount", "Currency", "Date", "Sign", "Tags", "Konto",
+        ]))
+        .unwrap();
+        let transaction = TransactionParser::new()
+            .build_from_vec(
+                &mapping,
+                &header(&[
+                    "Salary",
+                    "3500,00",
+                    "CHF",
+                    "25.01.2024",
+                    "+",
+                    "work, monthly",
+                    "Bank",
+                ]),
+            )
+            .unwrap();
+
+        assert_eq!(transaction.amount(), Amount::new(3500.0, Currency::chf()));
+        assert_eq!(
+            transaction.date(),
+            NaiveDate::from_ymd_opt(2024, 1, 25).unwrap()
+        );
+        assert_eq!(transaction.transaction_type(), TransactionType::Unknown);
+        assert_eq!(transaction.note().unwrap(), "Salary");
+        assert_eq!(transaction.tags, vec!["work", "monthly", "account:Bank"]);
+
+        let refund = TransactionParser::new()
+            .build_from_vec(
+                &ColumnMapping::default(),
+                &header(&["26.01.2024", "€", "+19.90", "C", "Refund"]),
+            )
+            .unwrap();
+        assert_eq!(refund.amount(), Amount::new(19.9, Currency::eur()));
+
+        assert_eq!(
+            ColumnMapping::from_header(&header(&["A", "B", "C"])).unwrap(),
+            ColumnMapping::default()
+        );
+        assert_eq!(
+            ColumnMapping::from_header(&header(&["Datum", "Betrag"]))
+                .unwrap()
+                .currency,
+            None
+        );
+        assert!(ColumnMapping::from_header(&header(&["Datum", "Währung"])).is_err());
+    }
+
+    #[test]
+    fn parse_tables() {
+        let tables = vec![
+            Table {
+                heading: Some("Januar".to_owned()),
+                header: row(1, &["Datum", "Währung", "Betrag", "Typ", "Kommentar"]),
+                rows: vec![
+                    row(3, &["15.01.2024", "€", "12.50", "E", "Migros"]),
+                    row(4, &["16.01.2024", "€", "abc", "E", "Coop"]),
+                ],
+            },
+            Table {
+                heading: None,
+                header: row(8, &["Date", "Amount"]),
+                rows: vec![row(9, &["01.02.2024", "10"])],
+            },
+            Table {
+                heading: None,
+                header: row(11, &["Date", "Currency"]),
+                rows: vec![row(12, &["01.03.2024", "€"])],
+            },
+        ];
+
+        let result =
+            TransactionParser::new().parse_tables(tables, RowCollector::new(|t| Ok(Some(t)), None));
+        assert_eq!(result.transactions.len(), 2);
+        // Without currency column the amount is in the base currency
+        assert_eq!(result.transactions[1].amount.currency, Currency::base());
+        assert_eq!(
+            result.errors,
+            vec![
+                Diagnostic::new(
+                    "Could not parse amount 'abc': invalid float literal",
+                    SourceSpan::line(4).with_column(20, 3)
+                )
+                .with_source_line("| 16.01.2024 | € | abc | E | Coop |")
+                .into(),
+                Diagnostic::new(
+                    "Table without heading: Missing column 'betrag/amount' in the header row",
+                    SourceSpan::line(11)
+                )
+                .with_source_line("| Date | Currency |")
+                .into(),
+            ]
+        );
+    }
 }
@@ -1,9 +1,141 @@
-use crate::{error::Error, transaction::Transaction};
+use crate::{
+    error::{Error, Res},
+    transaction::Transaction,
+};
+use std::path::Path;
 
+pub mod camt;
+pub mod csv;
+pub mod diagnostic;
+pub mod journal;
 pub mod json;
 pub mod markdown;
+pub mod mt940;
+pub mod ofx;
+pub mod qif;
+pub mod report;
 
 pub struct ImportResult {
     pub transactions: Vec<Transaction>,
     pub errors: Vec<Error>,
 }
+
+/// Collects the rows of a file while an importer reads it
+///
+/// Each parsed row is completed by `prepare_transaction`. Importers stop reading once
+/// `max_errors` rows failed, so no further rows are parsed or prompted for
+pub struct RowCollector<T> {
+    prepare_transaction: T,
+    max_errors: Option<usize>,
+    transactions: Vec<Transaction>,
+    errors: Vec<Error>,
+}
+
+impl<T> RowCollector<T>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    pub fn new(prepare_transaction: T, max_errors: Option<usize>) -> Self {
+        Self {
+            prepare_transaction,
+            max_errors,
+            transactions: vec![],
+            errors: vec![],
+        }
+    }
+
+    /// Prepare a parsed row or record the error of a row that failed
+    pub fn push(&mut self, row: Res<Transaction>) {
+        self.push_located(row, |error| error)
+    }
+
+    /// Like `push`, `locate` adds the position of the row to its error
+    pub fn push_located<L: FnOnce(Error) -> Error>(&mut self, row: Res<Transaction>, locate: L) {
+        match row.and_then(&mut self.prepare_transaction) {
+            Ok(Some(transaction)) => self.transactions.push(transaction),
+            Ok(None) => {}
+            Err(e) => self.errors.push(locate(e)),
+        }
+    }
+
+    /// Return `true` once `max_errors` rows failed and the importer has to stop
+    pub fn is_full(&self) -> bool {
+        self.max_errors
+            .is_some_and(|max_errors| self.errors.len() >= max_errors)
+    }
+
+    pub fn finish(self) -> ImportResult {
+        ImportResult {
+            transactions: self.transactions,
+            errors: self.errors,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ImportFormat {
+    Camt,
+    Csv,
+    Journal,
+    Json,
+    Markdown,
+    Mt940,
+    Ndjson,
+    Ofx,
+    Qif,
+}
+
+/// Prefix a bank reference with the importer and the account it belongs to
+///
+/// References such as CAMT's `AcctSvcrRef` or OFX's `FITID` are only unique per account, while
+/// duplicates are looked up by external ID across all transactions
+pub fn scoped_external_id(format: ImportFormat, account: Option<&str>, reference: &str) -> String {
+    format!(
+        "{}:{}:{}",
+        format.name(),
+        account.unwrap_or_default(),
+        reference
+    )
+}
+
+impl ImportFormat {
+    /// Name of the importer, as recorded with each import batch
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImportFormat::Camt => "camt",
+            ImportFormat::Csv => "csv",
+            ImportFormat::Journal => "journal",
+            ImportFormat::Json => "json",
+            ImportFormat::Markdown => "markdown",
+            ImportFormat::Mt940 => "mt940",
+            ImportFormat::Ndjson => "ndjson",
+            ImportFormat::Ofx => "ofx",
+            ImportFormat::Qif => "qif",
+        }
+    }
+
+    /// Detect the format from the extension of `path`
+    pub fn from_path(path: &Path) -> Res<Self> {
+        match path
+            .extension()
+            .map(|e| e.to_str().expect("Path is not UTF8").to_lowercase())
+            .as_deref()
+        {
+            Some("xml") => Ok(ImportFormat::Camt),
+            Some("csv") => Ok(ImportFormat::Csv),
+            Some("json") => Ok(ImportFormat::Json),
+            Some("ledger") | Some("journal") | Some("hledger") | Some("beancount")
+            | Some("bean") => Ok(ImportFormat::Journal),
+            Some("md") => Ok(ImportFormat::Markdown),
+            Some("sta") | Some("mt940") => Ok(ImportFormat::Mt940),
+            Some("ndjson") | Some("jsonl") => Ok(ImportFormat::Ndjson),
+            Some("ofx") | Some("qfx") => Ok(ImportFormat::Ofx),
+            Some("qif") => Ok(ImportFormat::Qif),
+            Some(e) => Err(Error::Import(format!("No parser to import {} files", e))),
+            None => Err(Error::Import(format!(
+                "Could not detect the extension of file path {}",
+                path.display()
+            ))),
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use super::{scoped_external_id, ImportFormat, ImportResult, RowCollector};
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    transaction::{amount::Amount, transaction_type::TransactionType, Transaction},
+};
+use chrono::NaiveDate;
+use encoding_rs::WINDOWS_1252;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{fs, path::Path, str::FromStr};
+
+lazy_static! {
+    static ref FIELD_START: Regex = Regex::new(r"^:(\d{2}[A-Z]?):(.*)$").unwrap();
+    static ref STATEMENT_LINE: Regex = Regex::new(
+        r"(?s)^(\d{6})(\d{4})?(R?[DC])([A-Z])?(\d+,\d*)([NFS][A-Z0-9]{3})([^/\n]*)(?://([^\n]*))?(?:\n(.*))?$"
+    )
+    .unwrap();
+    static ref SUBFIELD: Regex = Regex::new(r"\?(\d{2})").unwrap();
+    static ref SEPA_TAG: Regex = Regex::new(r"(EREF|KREF|MREF|CRED|DEBT|SVWZ|ABWA|ABWE|IBAN|BIC)\+").unwrap();
+}
+
+/// Import the statement lines of a SWIFT MT940 file
+///
+/// A file may contain several statements, each one starts with a `:20:` field
+pub fn get_transactions<T, P: AsRef<Path>>(
+    input_file: P,
+    rows: RowCollector<T>,
+) -> Result<ImportResult, Error>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let bytes = fs::read(input_file)?;
+    // Most banks still export MT940 in Latin-1
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => WINDOWS_1252.decode(e.as_bytes()).0.into_owned(),
+    };
+
+    Ok(parse(&content, rows))
+}
+
+fn parse<T>(content: &str, mut rows: RowCollector<T>) -> ImportResult
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let mut currency: Option<Currency> = None;
+    let mut account: Option<String> = None;
+    let mut pending: Option<Res<Transaction>> = None;
+    let finish_pending = |pending: Option<Res<Transaction>>, rows: &mut RowCollector<T>| {
+        if let Some(transaction) = pending {
+            rows.push(transaction);
+        }
+    };
+
+    for (tag, value) in split_fields(content) {
+        match tag.as_str() {
+            "20" => {
+                finish_pending(pending.take(), &mut rows);
+                currency = None;
+                account = None;
+            }
+            "25" => account = Some(value.trim().to_owned()),
+            "60F" | "60M" => {
+                // Opening balance: D/C mark, date (YYMMDD), currency, amount
+                let raw_currency = value.get(7..10).unwrap_or_default();
+                match Currency::from_str(raw_currency) {
+                    Ok(c) => currency = Some(c),
+                    Err(e) => rows.push(Err(e)),
+                }
+            }
+            "61" => {
+                finish_pending(pending.take(), &mut rows);
+                pending = Some(match &currency {
+                    Some(currency) => parse_statement_line(&value, currency, account.as_deref()),
+                    None => Err(Error::Parse(format!(
+                        "Statement line '{}' appears before the opening balance",
+                        value
+                    ))),
+                });
+            }
+            "86" => {
+                if let Some(Ok(transaction)) = pending.as_mut() {
+                    let note = build_note(&value);
+                    if !note.is_empty() {
+                        transaction.note = Some(note);
+                    }
+                }
+                finish_pending(pending.take(), &mut rows);
+            }
+            _ => {}
+        }
+        if rows.is_full() {
+            return rows.finish();
+        }
+    }
+    finish_pending(pending.take(), &mut rows);
+
+    rows.finish()
+}
+
+/// Split the content into `(tag, value)` pairs, continuation lines are joined with `\n`
+fn split_fields(content: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = vec![];
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some(captures) = FIELD_START.captures(line) {
+            fields.push((captures[1].to_owned(), captures[2].to_owned()));
+        } else if line == "-" || line.starts_with("-}") || line.starts_with('{') {
+            // End of a statement or SWIFT block headers
+            continue;
+        } else if let Some((_, value)) = fields.last_mut() {
+            value.push('\n');
+            value.push_str(line);
+        }
+    }
+
+    fields
+}
+
+fn parse_statement_line(
+    value: &str,
+    currency: &Currency,
+    account: Option<&str>,
+) -> Res<Transaction> {
+    let captures = STATEMENT_LINE
+        .captures(value)
+        .ok_or_else(|| Error::Parse(format!("Could not parse statement line '{}'", value)))?;
+
+    // Two-digit years follow chrono's `%y`: 69-99 are 19xx, 00-68 are 20xx
+    let date = NaiveDate::parse_from_str(&captures[1], "%y%m%d")
+        .map_err(|e| Error::Parse(format!("Could not parse date '{}': {}", &captures[1], e)))?;
+
+    let raw_amount = &captures[5];
+    let value = raw_amount
+        .replace(',', ".")
+        .parse::<f64>()
+        .map_err(|e| Error::Parse(format!("Could not parse amount '{}': {}", raw_amount, e)))?;
+    // `RC` and `RD` mark the reversal of a credit or debit
+    let value = match &captures[3] {
+        "D" | "RC" => -value,
+        _ => value,
+    };
+
+    let customer_reference = captures[7].trim();
+    let bank_reference = captures.get(8).map(|m| m.as_str().trim());
+    let reference = match bank_reference {
+        Some(r) if !r.is_empty() => Some(r),
+        _ if !customer_reference.is_empty() && customer_reference != "NONREF" => {
+            Some(customer_reference)
+        }
+        _ => None,
+    };
+
+    let transaction = Transaction::new(
+        date,
+        Amount::new(value, currency.clone()),
+        None,
+        TransactionType::Unknown,
+        captures.get(9).map(|m| m.as_str().trim().to_owned()),
+    );
+
+    Ok(match reference {
+        Some(reference) => transaction.with_external_id(scoped_external_id(
+            ImportFormat::Mt940,
+            account,
+            reference,
+        )),
+        None => transaction,
+    })
+}
+
+/// Build a readable note from the `:86:` information field
+///
+/// Structured fields (`GVC?00…?20…`) are reduced to the counterparty's name and the purpose,
+/// unstructured fields are joined into a single line
+fn build_note(value: &str) -> String {
+    let is_structured = value.len() > 4 && value.as_bytes()[3] == b'?';
+    if !is_structured {
+        return value.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+    }
+
+    // Subfields are wrapped at a fixed width, so line breaks carry no meaning
+    let joined: String = value.lines().collect();
+    let mut subfields: Vec<(u8, &str)> = vec![];
+    let positions: Vec<(usize, usize, u8)> = SUBFIELD
+        .captures_iter(&joined)
+        .map(|c| {
+            let m = c.get(0).unwrap();
+            (m.start(), m.end(), c[1].parse().unwrap_or_default())
+        })
+        .collect();
+    for (index, (_, end, code)) in positions.iter().enumerate() {
+        let next_start = positions
+            .get(index + 1)
+            .map_or(joined.len(), |(start, _, _)| *start);
+        subfields.push((*code, &joined[*end..next_start]));
+    }
+
+    let collect = |codes: &[std::ops::RangeInclusive<u8>]| -> String {
+        subfields
+            .iter()
+            .filter(|(code, _)| codes.iter().any(|range| range.contains(code)))
+            .map(|(_, text)| *text)
+            .collect::<String>()
+            .trim()
+            .to_owned()
+    };
+    let booking_text = collect(&[0..=0]);
+    let name = collect(&[32..=33]);
+    let purpose = extract_sepa_purpose(&collect(&[20..=29, 60..=63]));
+
+    let parts: Vec<String> = [name, purpose]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
+        booking_text
+    } else {
+        parts.join(" - ")
+    }
+}
+
+/// Return the `SVWZ+` section of a SEPA purpose, or the whole purpose if it is not tagged
+fn extract_sepa_purpose(purpose: &str) -> String {
+    let tags: Vec<(usize, usize, &str)> = SEPA_TAG
+        .captures_iter(purpose)
+        .map(|c| {
+            let m = c.get(0).unwrap();
+            (m.start(), m.end(), c.get(1).unwrap().as_str())
+        })
+        .collect();
+
+    let svwz = tags
+        .iter()
+        .enumerate()
+        .find(|(_, (_, _, tag))| *tag == "SVWZ");
+    match svwz {
+        Some((index, (_, end, _))) => {
+            let next_start = tags.get(index + 1).map_or(purpose.len(), |t| t.0);
+            purpose[*end..next_start].trim().to_owned()
+        }
+        None if tags.is_empty() => purpose.to_owned(),
+        None => purpose[..tags[0].0].trim().to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATEMENTS: &str = ":20:STARTUMS
+:25:12345678/0001234567
+:28C:00001/001
+:60F:C240301EUR1000,00
+:61:2403040304DR45,90NDDTNONREF//BANKREF1
+:86:105?00LASTSCHRIFT?20EREF+1234 MREF+M-9 CRED+DE98Z?21ZZZ09999999999SVWZ+Abschlag
+?22 Strom Maerz?30BYLADEMMXXX?31DE02120300000000202051?32Stadtwerke Musterstadt
+:61:240325C2500,00NTRFNONREF
+:86:Gehalt Maerz
+Arbeitgeber GmbH
+:62F:C240331EUR3454,10
+-
+:20:STARTUMS
+:25:12345678/0007654321
+:60F:C240301CHF10,00
+:61:240305RC5,00NTRFREF-42
+:86:166?00GUTSCHRIFT?32Shop AG
+-";
+
+    #[test]
+    fn parse_statements() {
+        let result = parse(STATEMENTS, RowCollector::new(|t| Ok(Some(t)), None));
+
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.transactions.len(), 3);
+
+        let debit = &result.transactions[0];
+        assert_eq!(debit.amount, Amount::new(-45.9, Currency::eur()));
+        assert_eq!(debit.date, NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(
+            debit.note,
+            Some("Stadtwerke Musterstadt - Abschlag Strom Maerz".to_owned())
+        );
+        assert_eq!(
+            debit.external_id,
+            Some("mt940:12345678/0001234567:BANKREF1".to_owned())
+        );
+
+        let credit = &result.transactions[1];
+        assert_eq!(credit.amount, Amount::new(2500.0, Currency::eur()));
+        assert_eq!(
+            credit.note,
+            Some("Gehalt Maerz Arbeitgeber GmbH".to_owned())
+        );
+        assert_eq!(credit.external_id, None);
+
+        let reversal = &result.transactions[2];
+        assert_eq!(reversal.amount, Amount::new(-5.0, Currency::chf()));
+        assert_eq!(reversal.note, Some("Shop AG".to_owned()));
+        assert_eq!(
+            reversal.external_id,
+            Some("mt940:12345678/0007654321:REF-42".to_owned())
+        );
+    }
+
+    #[test]
+    fn statement_line_without_opening_balance() {
+        let result = parse(
+            ":20:X\n:61:240305D5,00NTRFNONREF\n",
+            RowCollector::new(|t| Ok(Some(t)), None),
+        );
+
+        assert!(result.transactions.is_empty());
+        assert_eq!(result.errors.len(), 1);
+    }
+
+    #[test]
+    fn statement_line_from_the_last_century() {
+        let result = parse(
+            ":20:X\n:60F:C981201EUR0,00\n:61:981231D5,00NTRFNONREF\n",
+            RowCollector::new(|t| Ok(Some(t)), None),
+        );
+
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(
+            result.transactions[0].date,
+            NaiveDate::from_ymd_opt(1998, 12, 31).unwrap()
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use super::{scoped_external_id, ImportFormat, ImportResult, RowCollector};
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    transaction::{amount::Amount, transaction_type::TransactionType, Transaction},
+};
+use chrono::NaiveDate;
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+
+/// Import the `STMTTRN` records of an OFX/QFX file
+///
+/// OFX 1.x (SGML, leaf elements without closing tags) and OFX 2.x (XML) are read by the same
+/// tokenizer. The `FITID` is kept as the external ID, so records that have already been imported
+/// are recognized
+pub fn get_transactions<T, P: AsRef<Path>>(
+    input_file: P,
+    rows: RowCollector<T>,
+) -> Result<ImportResult, Error>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let bytes = fs::read(input_file)?;
+    let content = String::from_utf8_lossy(&bytes);
+
+    Ok(parse(&content, rows))
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Open(&'a str),
+    Close(&'a str),
+    Text(&'a str),
+}
+
+fn parse<T>(content: &str, mut rows: RowCollector<T>) -> ImportResult
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let mut default_currency: Option<String> = None;
+    let mut account: Option<String> = None;
+    let mut record: Option<HashMap<&str, String>> = None;
+    let mut current_element: Option<&str> = None;
+    let mut number_of_records = 0;
+    for token in tokenize(content) {
+        match token {
+            Token::Open("STMTTRN") => {
+                record = Some(HashMap::new());
+                number_of_records += 1;
+            }
+            Token::Close("STMTTRN") => {
+                let transaction = record
+                    .take()
+                    .ok_or_else(|| Error::Parse("Unexpected </STMTTRN>".to_owned()))
+                    .and_then(|r| {
+                        build_transaction(&r, default_currency.as_deref(), account.as_deref())
+                    })
+                    .map_err(|e| {
+                        Error::Parse(format!("Record {}: {}", number_of_records, e.message()))
+                    });
+
+                rows.push(transaction);
+                if rows.is_full() {
+                    break;
+                }
+            }
+            Token::Open(name) => current_element = Some(name),
+            Token::Close(_) => current_element = None,
+            Token::Text(text) => {
+                if let Some(name) = current_element.take() {
+                    let value = decode_entities(text);
+                    match record.as_mut() {
+                        Some(record) => {
+                            record.insert(name, value);
+                        }
+                        None if name == "CURDEF" => default_currency = Some(value),
+                        None if name == "ACCTID" => account = Some(value),
+                        None => {}
+                    }
+                }
+            }
+        }
+    }
+
+    rows.finish()
+}
+
+fn build_transaction(
+    record: &HashMap<&str, String>,
+    default_currency: Option<&str>,
+    account: Option<&str>,
+) -> Res<Transaction> {
+    let get = |name: &str| {
+        record
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| Error::Parse(format!("Missing <{}>", name)))
+    };
+
+    // Dates are written as `YYYYMMDD[HHMMSS[.XXX][[-5:EST]]]`
+    let raw_date = get("DTPOSTED")?;
+    let date = NaiveDate::parse_from_str(raw_date.get(..8).unwrap_or(raw_date), "%Y%m%d")
+        .map_err(|e| Error::Parse(format!("Could not parse date '{}': {}", raw_date, e)))?;
+
+    let raw_amount = get("TRNAMT")?;
+    let value = raw_amount
+        .replace(',', ".")
+        .parse::<f64>()
+        .map_err(|e| Error::Parse(format!("Could not parse amount '{}': {}", raw_amount, e)))?;
+
+    let currency = match default_currency {
+        Some(c) => Currency::from_str(c)?,
+        None => return Err(Error::Parse("Statement defines no <CURDEF>".to_owned())),
+    };
+
+    let note = [record.get("NAME"), record.get("MEMO")]
+        .into_iter()
+        .flatten()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    // `MEMO` often repeats the `NAME`
+    let note = if note.len() == 2 && note[1].starts_with(note[0]) {
+        note[1].to_owned()
+    } else {
+        note.join(" - ")
+    };
+
+    let transaction = Transaction::new(
+        date,
+        Amount::new(value, currency),
+        None,
+        TransactionType::Unknown,
+        Some(note),
+    );
+
+    Ok(match record.get("FITID") {
+        Some(fitid) => transaction.with_external_id(scoped_external_id(
+            ImportFormat::Ofx,
+            account,
+            fitid.trim(),
+        )),
+        None => transaction,
+    })
+}
+
+/// Split the content into tags and text, the SGML header before `<OFX>` is skipped
+fn tokenize(content: &str) -> Vec<Token<'_>> {
+    let body = match content.find("<OFX>") {
+        Some(start) => &content[start..],
+        None => content,
+    };
+
+    let mut tokens = vec![];
+    let mut rest = body;
+    while let Some(start) = rest.find('<') {
+        let text = rest[..start].trim();
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let tag = rest[start + 1..end].trim();
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::Close(name.trim()));
+        } else if !tag.starts_with('?') && !tag.starts_with('!') {
+            let name = tag.split_whitespace().next().unwrap_or_default();
+            tokens.push(Token::Open(name.trim_end_matches('/')));
+        }
+        rest = &rest[end + 1..];
+    }
+
+    tokens
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SGML: &str = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+
+<OFX>
+<CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS>
+<CURDEF>USD
+<CCACCTFROM>
+<ACCTID>4111111111111111
+</CCACCTFROM>
+<BANKTRANLIST>
+<DTSTART>20240101
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20240115120000.000[-5:EST]
+<TRNAMT>-42.10
+<FITID>2024011501
+<NAME>AMAZON MKTPLACE
+<MEMO>Order 123 &amp; more
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20240120
+<TRNAMT>15.00
+<FITID>2024012002
+<NAME>REFUND
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<TRNAMT>1.00
+</STMTTRN>
+</BANKTRANLIST>
+</CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1>
+</OFX>";
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<?OFX OFXHEADER="200" VERSION="220"?>
+<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS>
+  <CURDEF>CHF</CURDEF>
+  <BANKTRANLIST>
+    <STMTTRN>
+      <TRNTYPE>DEBIT</TRNTYPE>
+      <DTPOSTED>20240301</DTPOSTED>
+      <TRNAMT>-9.80</TRNAMT>
+      <FITID>A-1</FITID>
+      <NAME>Migros</NAME>
+      <MEMO>Migros Bern</MEMO>
+    </STMTTRN>
+  </BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>"#;
+
+    #[test]
+    fn parse_sgml() {
+        let result = parse(SGML, RowCollector::new(|t| Ok(Some(t)), None));
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.transactions.len(), 2);
+
+        let transaction = &result.transactions[0];
+        assert_eq!(transaction.amount, Amount::new(-42.1, Currency::usd()));
+        assert_eq!(
+            transaction.date,
+            NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()
+        );
+        assert_eq!(
+            transaction.note,
+            Some("AMAZON MKTPLACE - Order 123 & more".to_owned())
+        );
+        assert_eq!(
+            transaction.external_id,
+            Some("ofx:4111111111111111:2024011501".to_owned())
+        );
+        assert_eq!(result.transactions[1].amount.value, 15.0);
+    }
+
+    #[test]
+    fn parse_xml() {
+        let result = parse(XML, RowCollector::new(|t| Ok(Some(t)), None));
+
+        assert!(result.errors.is_empty());
+        assert_eq!(result.transactions.len(), 1);
+        let transaction = &result.transactions[0];
+        assert_eq!(transaction.amount, Amount::new(-9.8, Currency::chf()));
+        assert_eq!(transaction.note, Some("Migros Bern".to_owned()));
+        assert_eq!(transaction.external_id, Some("ofx::A-1".to_owned()));
+    }
+}
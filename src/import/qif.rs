@@ -0,0 +1,316 @@
+use super::{ImportResult, RowCollector};
+use crate::{
+    category_mapping::CategoryMapping,
+    currency::Currency,
+    error::{Error, Res},
+    transaction::{amount::Amount, transaction_type::TransactionType, Transaction},
+};
+use chrono::NaiveDate;
+use std::{fs, path::Path};
+
+/// Date formats tried if no explicit format is configured
+///
+/// Two-digit years come first, because `%Y` would accept `24` as the year 24
+const DEFAULT_DATE_FORMATS: [&str; 5] =
+    ["%m/%d/%y", "%m/%d/%Y", "%d.%m.%y", "%d.%m.%Y", "%Y-%m-%d"];
+
+pub struct QifOptions {
+    /// Date format in `chrono` syntax (`'` in QIF dates is treated as `/`)
+    pub date_format: Option<String>,
+    /// QIF files carry no currency information
+    pub currency: Currency,
+    pub category_mapping: CategoryMapping,
+}
+
+impl Default for QifOptions {
+    fn default() -> Self {
+        Self {
+            date_format: None,
+            currency: Currency::base(),
+            category_mapping: CategoryMapping::default(),
+        }
+    }
+}
+
+/// Import the `Bank`, `Cash` and `CCard` sections of a QIF file
+pub fn get_transactions<T, P: AsRef<Path>>(
+    input_file: P,
+    options: &QifOptions,
+    rows: RowCollector<T>,
+) -> Result<ImportResult, Error>
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let bytes = fs::read(input_file)?;
+    let content = String::from_utf8_lossy(&bytes);
+
+    Ok(parse(&content, options, rows))
+}
+
+#[derive(Default)]
+struct Record {
+    line: usize,
+    date: Option<String>,
+    amount: Option<String>,
+    payee: Option<String>,
+    memo: Option<String>,
+    category: Option<String>,
+    splits: Vec<Split>,
+}
+
+#[derive(Default)]
+struct Split {
+    category: Option<String>,
+    memo: Option<String>,
+    amount: Option<String>,
+}
+
+pub(crate) fn parse<T>(
+    content: &str,
+    options: &QifOptions,
+    mut rows: RowCollector<T>,
+) -> ImportResult
+where
+    T: FnMut(Transaction) -> Res<Option<Transaction>>,
+{
+    let mut in_supported_section = false;
+    let mut record = Record::default();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.starts_with('!') {
+            let header = line.trim().to_lowercase();
+            in_supported_section = matches!(
+                header.as_str(),
+                "!type:bank" | "!type:cash" | "!type:ccard" | "!type:oth a" | "!type:oth l"
+            );
+            record = Record::default();
+            continue;
+        }
+        if !in_supported_section {
+            continue;
+        }
+
+        if record.line == 0 {
+            record.line = index + 1;
+        }
+        let (code, value) = line.split_at(line.chars().next().map_or(0, char::len_utf8));
+        let value = value.trim().to_owned();
+        match code {
+            "D" => record.date = Some(value),
+            "T" | "U" => record.amount = Some(value),
+            "P" => record.payee = Some(value),
+            "M" => record.memo = Some(value),
+            "L" => record.category = Some(value),
+            "S" => record.splits.push(Split {
+                category: Some(value),
+                ..Default::default()
+            }),
+            "E" => {
+                if let Some(split) = record.splits.last_mut() {
+                    split.memo = Some(value)
+                }
+            }
+            "$" => {
+                if let Some(split) = record.splits.last_mut() {
+                    split.amount = Some(value)
+                }
+            }
+            "^" => {
+                let finished = std::mem::take(&mut record);
+                match build_transactions(&finished, options) {
+                    Ok(built) => {
+                        for transaction in built {
+                            rows.push(Ok(transaction));
+                        }
+                    }
+                    Err(e) => rows.push(Err(Error::Parse(format!(
+                        "Record at line {}: {}",
+                        finished.line,
+                        e.message()
+                    )))),
+                }
+                if rows.is_full() {
+                    break;
+                }
+            }
+            // Number, cleared status, address and split percentages are not imported
+            _ => {}
+        }
+    }
+
+    rows.finish()
+}
+
+/// Build the transactions of a record, split records result in one transaction per split
+fn build_transactions(record: &Record, options: &QifOptions) -> Res<Vec<Transaction>> {
+    let raw_date = record
+        .date
+        .as_deref()
+        .ok_or_else(|| Error::Parse("Record has no date".to_owned()))?;
+    let date = parse_date(raw_date, options.date_format.as_deref())?;
+
+    if record.splits.is_empty() {
+        let raw_amount = record
+            .amount
+            .as_deref()
+            .ok_or_else(|| Error::Parse("Record has no amount".to_owned()))?;
+
+        return Ok(vec![Transaction::new(
+            date,
+            Amount::new(parse_amount(raw_amount)?, options.currency.clone()),
+            None,
+            find_type(record.category.as_deref(), options),
+            build_note(record.payee.as_deref(), record.memo.as_deref()),
+        )]);
+    }
+
+    record
+        .splits
+        .iter()
+        .map(|split| {
+            let raw_amount = split
+                .amount
+                .as_deref()
+                .ok_or_else(|| Error::Parse("Split has no amount".to_owned()))?;
+            let memo = split.memo.as_deref().or(record.memo.as_deref());
+
+            Ok(Transaction::new(
+                date,
+                Amount::new(parse_amount(raw_amount)?, options.currency.clone()),
+                None,
+                find_type(split.category.as_deref(), options),
+                build_note(record.payee.as_deref(), memo),
+            ))
+        })
+        .collect()
+}
+
+fn find_type(category: Option<&str>, options: &QifOptions) -> TransactionType {
+    // `Category/Class` - the class is not used
+    let category = category
+        .unwrap_or_default()
+        .split('/')
+        .next()
+        .unwrap_or_default();
+
+    options.category_mapping.find(category)
+}
+
+fn build_note(payee: Option<&str>, memo: Option<&str>) -> Option<String> {
+    let parts: Vec<&str> = [payee, memo]
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(" - "))
+    }
+}
+
+fn parse_date(raw_date: &str, date_format: Option<&str>) -> Res<NaiveDate> {
+    // Quicken writes dates like ` 1/ 5'24`
+    let prepared: String = raw_date
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| if c == '\'' { '/' } else { c })
+        .collect();
+
+    let result = match date_format {
+        Some(date_format) => NaiveDate::parse_from_str(&prepared, date_format).ok(),
+        None => DEFAULT_DATE_FORMATS
+            .iter()
+            .find_map(|date_format| NaiveDate::parse_from_str(&prepared, date_format).ok()),
+    };
+
+    result.ok_or_else(|| Error::Parse(format!("Could not parse date '{}'", raw_date)))
+}
+
+fn parse_amount(raw_amount: &str) -> Res<f64> {
+    raw_amount
+        .replace(',', "")
+        .parse::<f64>()
+        .map_err(|e| Error::Parse(format!("Could not parse amount '{}': {}", raw_amount, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QIF: &str = "!Type:Bank
+D01/15/2024
+T-1,234.50
+PLandlord
+MJanuary
+LRent
+^
+D1/20'24
+T-80.00
+PSupermarket
+LGroceries
+SGroceries
+EFood
+$-60.00
+SHousehold
+$-20.00
+^
+!Type:Invst
+D01/21/2024
+T-5.00
+^
+!Type:CCard
+D01/22/2024
+T10.00
+PRefund
+^
+DXX
+T1.00
+^
+";
+
+    #[test]
+    fn parse_qif() {
+        let result = parse(
+            QIF,
+            &QifOptions::default(),
+            RowCollector::new(|t| Ok(Some(t)), None),
+        );
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.transactions.len(), 4);
+
+        let rent = &result.transactions[0];
+        assert_eq!(rent.date, NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        assert_eq!(rent.amount, Amount::new(-1234.5, Currency::eur()));
+        assert_eq!(rent.transaction_type, TransactionType::Home);
+        assert_eq!(rent.note, Some("Landlord - January".to_owned()));
+
+        let groceries = &result.transactions[1];
+        assert_eq!(groceries.amount.value, -60.0);
+        assert_eq!(groceries.transaction_type, TransactionType::Eat);
+        assert_eq!(groceries.note, Some("Supermarket - Food".to_owned()));
+        assert_eq!(result.transactions[2].amount.value, -20.0);
+        assert_eq!(result.transactions[2].note, Some("Supermarket".to_owned()));
+
+        assert_eq!(result.transactions[3].amount.value, 10.0);
+        assert!(result.errors[0].to_string().contains("line 27"));
+    }
+
+    #[test]
+    fn parse_date_formats() {
+        let expected = NaiveDate::from_ymd_opt(2024, 2, 3).unwrap();
+        assert_eq!(parse_date("02/03/2024", None).unwrap(), expected);
+        assert_eq!(parse_date(" 2/ 3'24", None).unwrap(), expected);
+        assert_eq!(parse_date("03.02.2024", None).unwrap(), expected);
+        assert_eq!(
+            parse_date("03/02/2024", Some("%d/%m/%Y")).unwrap(),
+            expected
+        );
+        assert!(parse_date("2024", None).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{
+    calculator::Calculator,
+    currency::{
+        amount_converter::AmountConverter, exchange_rate_provider::ExchangeRateProvider, Currency,
+    },
+    duplicate_check::DuplicateChecker,
+    error::Error,
+    printer::PrinterTrait,
+    transaction::{transaction_type::TransactionType, Transaction},
+};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+/// Outcome of an import that has not been written to the database
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub base_currency: String,
+    pub new_transactions: Vec<ReportTransaction>,
+    pub possible_duplicates: Vec<ReportDuplicate>,
+    /// Rows whose external ID is already stored
+    pub already_imported: Vec<ReportTransaction>,
+    pub errors: Vec<String>,
+    /// Totals of the rows that would be added (new rows and possible duplicates)
+    pub totals: Vec<ReportTotal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportTransaction {
+    pub date: String,
+    pub amount: f64,
+    pub currency: String,
+    pub base_amount: Option<f64>,
+    pub type_identifier: char,
+    pub type_name: String,
+    pub note: Option<String>,
+    pub external_id: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportDuplicate {
+    pub transaction: ReportTransaction,
+    pub matches: Vec<ReportMatch>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportMatch {
+    #[serde(flatten)]
+    pub transaction: ReportTransaction,
+    pub score: f64,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportTotal {
+    pub type_identifier: char,
+    pub type_name: String,
+    pub count: usize,
+    /// Sum in the base currency, rows without an exchange rate are left out
+    pub total: f64,
+    pub without_rate: usize,
+}
+
+impl ReportTransaction {
+    fn new(transaction: &Transaction) -> Self {
+        Self {
+            date: transaction.date.format("%Y-%m-%d").to_string(),
+            amount: transaction.amount.value,
+            currency: transaction.amount.currency.iso.clone(),
+            base_amount: transaction.base_amount.as_ref().map(|a| a.value),
+            type_identifier: transaction.transaction_type.identifier(),
+            type_name: transaction.transaction_type.name().to_owned(),
+            note: transaction.note.clone(),
+            external_id: transaction.external_id.clone(),
+            tags: transaction.tags.clone(),
+        }
+    }
+}
+
+impl ImportReport {
+    pub fn new(
+        transactions: &[Transaction],
+        errors: &[Error],
+        already_imported: &[Transaction],
+        current_transactions: &[Transaction],
+        duplicate_checker: &DuplicateChecker,
+        exchange_rate_provider: &ExchangeRateProvider,
+    ) -> Self {
+        let base_currency = Currency::base();
+        let transactions: Vec<Transaction> = transactions
+            .iter()
+            .map(|t| {
+                AmountConverter::convert_to_currency(
+                    t.clone(),
+                    &base_currency,
+                    exchange_rate_provider,
+                )
+            })
+            .collect();
+
+        let mut new_transactions = vec![];
+        let mut possible_duplicates = vec![];
+        for transaction in &transactions {
+            let matches = duplicate_checker.find_candidates(transaction, current_transactions);
+            if matches.is_empty() {
+                new_transactions.push(ReportTransaction::new(transaction));
+            } else {
+                possible_duplicates.push(ReportDuplicate {
+                    transaction: ReportTransaction::new(transaction),
+                    matches: matches
+                        .into_iter()
+                        .map(|candidate| ReportMatch {
+                            transaction: ReportTransaction::new(candidate.transaction),
+                            score: candidate.score,
+                            reasons: candidate.reasons.iter().map(|r| r.to_string()).collect(),
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        let totals = TransactionType::all()
+            .into_iter()
+            .filter_map(|transaction_type| {
+                let of_type: Vec<Transaction> = transactions
+                    .iter()
+                    .filter(|t| t.transaction_type == transaction_type)
+                    .cloned()
+                    .collect();
+                if of_type.is_empty() {
+                    return None;
+                }
+
+                Some(ReportTotal {
+                    type_identifier: transaction_type.identifier(),
+                    type_name: transaction_type.name().to_owned(),
+                    count: of_type.len(),
+                    total: Calculator::sum(&of_type),
+                    without_rate: of_type.iter().filter(|t| t.base_amount.is_none()).count(),
+                })
+            })
+            .collect();
+
+        Self {
+            base_currency: base_currency.iso,
+            new_transactions,
+            possible_duplicates,
+            already_imported: already_imported
+                .iter()
+                .map(ReportTransaction::new)
+                .collect(),
+            errors: errors.iter().map(|e| e.to_string()).collect(),
+            totals,
+        }
+    }
+
+    pub fn print<P: PrinterTrait>(&self, printer: &mut P, format: ReportFormat) {
+        match format {
+            ReportFormat::Json => printer.println(
+                serde_json::to_string_pretty(self).expect("Report can always be serialized"),
+            ),
+            ReportFormat::Text => self.print_text(printer),
+        }
+    }
+
+    fn print_text<P: PrinterTrait>(&self, printer: &mut P) {
+        printer.print_header("Dry run, nothing has been written");
+
+        printer.print_subheader(format!(
+            "New transactions ({})",
+            self.new_transactions.len()
+        ));
+        for transaction in &self.new_transactions {
+            printer.println(format!("  {}", format_row(transaction)));
+        }
+        printer.print_newline();
+
+        printer.print_subheader(format!(
+            "Possible duplicates ({})",
+            self.possible_duplicates.len()
+        ));
+        for duplicate in &self.possible_duplicates {
+            printer.println(format!("  {}", format_row(&duplicate.transaction)));
+            for existing in &duplicate.matches {
+                printer.println(format!(
+                    "    {:.0}% {} ({})",
+                    existing.score * 100.0,
+                    format_row(&existing.transaction),
+                    existing.reasons.join(", ")
+                ));
+            }
+        }
+        printer.print_newline();
+
+        if !self.already_imported.is_empty() {
+            printer.print_subheader(format!(
+                "Already imported ({})",
+                self.already_imported.len()
+            ));
+            for transaction in &self.already_imported {
+                printer.println(format!("  {}", format_row(transaction)));
+            }
+            printer.print_newline();
+        }
+
+        printer.print_subheader(format!("Parse errors ({})", self.errors.len()));
+        for error in &self.errors {
+            printer.print_warning(format!("  {}", error));
+        }
+        printer.print_newline();
+
+        printer.print_subheader("Totals of the rows to add");
+        for total in &self.totals {
+            let without_rate = if total.without_rate > 0 {
+                format!(" ({} without exchange rate)", total.without_rate)
+            } else {
+                "".to_owned()
+            };
+            printer.println(format!(
+                "  {:<18} {:>4} × {} {:>10.2}{}",
+                total.type_name, total.count, self.base_currency, total.total, without_rate
+            ));
+        }
+    }
+}
+
+fn format_row(transaction: &ReportTransaction) -> String {
+    format!(
+        "{} {} {:>10.2} [{}] {}",
+        transaction.date,
+        transaction.currency,
+        transaction.amount,
+        transaction.type_identifier,
+        transaction.note.as_deref().unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::amount::Amount;
+    use chrono::NaiveDate;
+
+    fn transaction(day: u32, value: f64, transaction_type: TransactionType) -> Transaction {
+        Transaction::new(
+            NaiveDate::from_ymd_opt(2024, 3, day).unwrap(),
+            Amount::new(value, Currency::eur()),
+            None,
+            transaction_type,
+            Some("Note".to_owned()),
+        )
+    }
+
+    #[test]
+    fn build_report() {
+        let existing = vec![transaction(1, -10.0, TransactionType::Eat)];
+        let incoming = vec![
+            transaction(2, -10.0, TransactionType::Eat),
+            transaction(9, -5.0, TransactionType::Eat),
+            transaction(9, -20.0, TransactionType::Gas),
+        ];
+        let errors = vec![Error::Parse("Row 4: Could not parse amount".to_owned())];
+
+        let report = ImportReport::new(
+            &incoming,
+            &errors,
+            &[],
+            &existing,
+            &DuplicateChecker::default(),
+            &ExchangeRateProvider::new(vec![]),
+        );
+
+        assert_eq!(report.new_transactions.len(), 2);
+        assert_eq!(report.possible_duplicates.len(), 1);
+        assert_eq!(
+            report.possible_duplicates[0].matches[0].transaction.date,
+            "2024-03-01"
+        );
+        assert_eq!(
+            report.errors,
+            vec!["Parse Error: Row 4: Could not parse amount"]
+        );
+        assert_eq!(report.totals.len(), 2);
+        assert_eq!(report.totals[0].count, 2);
+        assert_eq!(report.totals[0].total, -15.0);
+    }
+}
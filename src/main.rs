@@ -1,21 +1,36 @@
+use crate::category_mapping::CategoryMapping;
+use crate::commands::watch::WatchOptions;
 use crate::currency::Currency;
+use crate::duplicate_check::{DuplicateCheckOptions, DuplicatePolicy};
+use crate::error::Res;
+use crate::export::csv::{CsvColumn, CsvOptions};
+use crate::export::{ExportFormat, ExportOptions};
+use crate::filter::Request;
+use crate::import::report::ReportFormat;
+use crate::import::ImportFormat;
 use crate::printer::Printer;
+use crate::transaction::amount::Amount;
 use crate::transaction::transaction_type::TransactionType;
 use crate::verbosity::Verbosity;
-use clap::{arg, command, Parser, Subcommand};
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
 mod calculator;
+mod category_mapping;
+mod classifier;
 mod commands;
 mod currency;
 mod duplicate_check;
 mod error;
+mod export;
 mod file;
 mod filter;
 mod import;
 mod month;
 mod persistence;
 mod printer;
+mod rules;
 mod transaction;
 mod verbosity;
 mod wizard;
@@ -37,25 +52,12 @@ enum Commands {
         #[arg(value_name = "FILE")]
         input: PathBuf,
 
-        /// Show entries from this date
-        #[arg(short, long)]
-        from: Option<String>,
-
-        /// Show entries up to and including this date
-        #[arg(short('x'), long)]
-        to: Option<String>,
-
-        /// Filter by type
-        #[arg(short, long)]
-        r#type: Option<TransactionType>,
+        #[command(flatten)]
+        filter: FilterArguments,
 
-        /// Search-term to find in notes
-        #[arg(short, long)]
-        search: Option<String>,
-
-        /// Exclude transactions containing this search-term in notes
-        #[arg(short, long)]
-        exclude: Option<String>,
+        /// Currency to use for the report (defaults to the base currency)
+        #[arg(long, value_name = "CURRENCY")]
+        report_currency: Option<Currency>,
 
         /// Level of verbosity
         #[arg(short, long, action = clap::ArgAction::Count)]
@@ -74,29 +76,363 @@ enum Commands {
         verbosity: u8,
     },
 
-    /// Import data from Markdown files into the database
+    /// Import data from Markdown, JSON, CSV or bank statement files into the database
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Import {
-        /// Markdown or JSON file to import
-        #[arg(value_name = "IMPORT-FILE")]
-        input: PathBuf,
+        #[command(subcommand)]
+        command: Option<ImportCommands>,
+
+        /// Markdown, JSON, NDJSON, CSV, CAMT.053 XML, MT940, OFX/QFX, QIF or ledger/beancount
+        /// files to import, directories, glob patterns or `-` for stdin
+        #[arg(value_name = "IMPORT-FILE", required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
 
         /// Budget file to use
-        #[arg(value_name = "DATABASE")]
-        output: PathBuf,
+        #[arg(value_name = "DATABASE", required = true)]
+        output: Option<PathBuf>,
+
+        /// Format of the imported files (required for stdin, detected from the extension
+        /// otherwise)
+        #[arg(short, long, value_enum)]
+        format: Option<ImportFormat>,
+
+        /// CSV mapping profile (path to a JSON file or name of a file in `profiles/` next to the
+        /// database)
+        #[arg(short, long)]
+        profile: Option<String>,
+
+        /// Date format of QIF files in `chrono` syntax (e.g. "%d/%m/%Y")
+        #[arg(long)]
+        date_format: Option<String>,
+
+        /// JSON file that maps QIF categories and journal accounts to types
+        #[arg(long, value_name = "FILE")]
+        category_map: Option<PathBuf>,
+
+        /// JSON file with categorization rules (defaults to the rules stored in the database)
+        #[arg(long, value_name = "FILE")]
+        rules: Option<PathBuf>,
+
+        /// Minimum confidence (0 to 1) to assign the suggested type without user input
+        #[arg(long, default_value_t = 0.9, value_parser = parse_confidence)]
+        auto_assign_threshold: f64,
+
+        #[command(flatten)]
+        duplicates: DuplicateArguments,
+
+        /// What to do with possible duplicates when there is no user interaction
+        #[arg(long, value_enum, default_value_t = DuplicatePolicy::Keep)]
+        on_duplicate: DuplicatePolicy,
 
         /// Require no user input during import
         #[arg(long)]
         no_interaction: bool,
 
+        /// Show what would be imported without writing to the database
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Format of the dry-run report
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text, requires = "dry_run")]
+        report: ReportFormat,
+
+        /// Abort the import once this many rows could not be parsed
+        #[arg(long, value_name = "COUNT", value_parser = clap::value_parser!(u64).range(1..))]
+        max_errors: Option<u64>,
+
         /// Level of verbosity
         #[arg(short, long, action = clap::ArgAction::Count)]
         verbosity: u8,
     },
 
+    /// Export transactions into other formats
+    Export {
+        /// Budget file to use
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+
+        #[command(flatten)]
+        filter: FilterArguments,
+
+        /// Format to export
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+
+        /// File to write (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Date format in `chrono` syntax (e.g. "%d.%m.%Y")
+        #[arg(long)]
+        date_format: Option<String>,
+
+        /// JSON file that maps category (or account) names to types
+        #[arg(long, value_name = "FILE")]
+        category_map: Option<PathBuf>,
+
+        /// Account that pays for the transactions in ledger and beancount exports
+        #[arg(long)]
+        funding_account: Option<String>,
+
+        /// Columns of the CSV export, separated by commas
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = CsvColumn::defaults())]
+        columns: Vec<CsvColumn>,
+
+        /// Field delimiter of the CSV export (use ';' for German Excel)
+        #[arg(long, default_value_t = ',')]
+        delimiter: char,
+
+        /// Decimal separator of the CSV export
+        #[arg(long, default_value_t = '.')]
+        decimal_separator: char,
+    },
+
+    /// Convert an amount into another currency
+    #[command(allow_negative_numbers = true)]
+    Convert {
+        /// Amount to convert
+        amount: f64,
+
+        /// Currency of the amount
+        currency: Currency,
+
+        /// Currency to convert into (defaults to the base currency)
+        #[arg(long, value_name = "CURRENCY")]
+        to: Option<Currency>,
+
+        /// Date of the exchange rate (YYYY-MM-DD, defaults to today)
+        #[arg(long)]
+        date: Option<NaiveDate>,
+
+        /// Budget file to read additional exchange rates from
+        #[arg(short, long, value_name = "FILE")]
+        file: Option<PathBuf>,
+    },
+
+    /// Apply the categorization rules to stored transactions
+    Recategorize {
+        /// Budget file to use
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+
+        #[command(flatten)]
+        filter: FilterArguments,
+
+        /// JSON file with categorization rules (defaults to the rules stored in the database)
+        #[arg(long, value_name = "FILE")]
+        rules: Option<PathBuf>,
+
+        /// Only show the changes without saving them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Find possible duplicates in the database and merge or delete them
+    Dedupe {
+        /// Budget file to use
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+
+        #[command(flatten)]
+        filter: FilterArguments,
+
+        #[command(flatten)]
+        duplicates: DuplicateArguments,
+
+        /// Only print the clusters of possible duplicates
+        #[arg(long)]
+        report: bool,
+    },
+
+    /// Import files as they arrive in a directory
+    Watch {
+        /// Directory to watch for new files
+        #[arg(value_name = "DIRECTORY")]
+        directory: PathBuf,
+
+        /// Budget file to use
+        #[arg(value_name = "DATABASE")]
+        output: PathBuf,
+
+        /// Log file (defaults to `watch.log` in the watched directory)
+        #[arg(long, value_name = "FILE")]
+        log: Option<PathBuf>,
+
+        /// CSV mapping profile (path to a JSON file or name of a file in `profiles/` next to the
+        /// database)
+        #[arg(short, long)]
+        profile: Option<String>,
+
+        /// JSON file that maps QIF categories and journal accounts to types
+        #[arg(long, value_name = "FILE")]
+        category_map: Option<PathBuf>,
+
+        /// JSON file with categorization rules (defaults to the rules stored in the database)
+        #[arg(long, value_name = "FILE")]
+        rules: Option<PathBuf>,
+
+        /// Minimum confidence (0 to 1) to assign the suggested type
+        #[arg(long, default_value_t = 0.9, value_parser = parse_confidence)]
+        auto_assign_threshold: f64,
+
+        #[command(flatten)]
+        duplicates: DuplicateArguments,
+
+        /// What to do with possible duplicates
+        #[arg(long, value_enum, default_value_t = DuplicatePolicy::Skip)]
+        on_duplicate: DuplicatePolicy,
+    },
+
+    /// Manage the rules that categorize transactions
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommands,
+    },
+
     /// Display the available types
     ShowTypes {},
 }
 
+#[derive(Subcommand)]
+enum ImportCommands {
+    /// List the recorded import batches
+    Batches {
+        /// Budget file to use
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+    },
+
+    /// Remove an import batch and all transactions it inserted, and undo its replaces and merges
+    Rollback {
+        /// Number of the batch
+        #[arg(value_name = "BATCH")]
+        batch: i64,
+
+        /// Budget file to use
+        #[arg(value_name = "DATABASE")]
+        output: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum RulesCommands {
+    /// List the rules stored in the database
+    List {
+        /// Budget file to use
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+    },
+
+    /// Replace the stored rules with the rules of a JSON file
+    Load {
+        /// JSON file with the rules
+        #[arg(value_name = "RULES-FILE")]
+        rules: PathBuf,
+
+        /// Budget file to use
+        #[arg(value_name = "DATABASE")]
+        output: PathBuf,
+    },
+
+    /// Show which rule matches a transaction
+    #[command(allow_negative_numbers = true)]
+    Test {
+        /// Budget file to use
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+
+        /// Amount of the transaction (expenses are negative)
+        #[arg(short, long)]
+        amount: f64,
+
+        /// Currency of the amount (defaults to the base currency)
+        #[arg(short, long)]
+        currency: Option<Currency>,
+
+        /// Date of the transaction (YYYY-MM-DD, defaults to today)
+        #[arg(short, long)]
+        date: Option<NaiveDate>,
+
+        /// Note of the transaction
+        #[arg(short, long, default_value = "")]
+        note: String,
+
+        /// JSON file with categorization rules (defaults to the rules stored in the database)
+        #[arg(long, value_name = "FILE")]
+        rules: Option<PathBuf>,
+    },
+}
+
+/// Arguments to select the transactions to work with
+#[derive(Args)]
+struct FilterArguments {
+    /// Show entries from this date
+    #[arg(short, long)]
+    from: Option<String>,
+
+    /// Show entries up to and including this date
+    #[arg(short('x'), long)]
+    to: Option<String>,
+
+    /// Filter by type
+    #[arg(short, long)]
+    r#type: Option<TransactionType>,
+
+    /// Search-term to find in notes
+    #[arg(short, long)]
+    search: Option<String>,
+
+    /// Exclude transactions containing this search-term in notes
+    #[arg(short, long)]
+    exclude: Option<String>,
+}
+
+impl FilterArguments {
+    fn into_request(self) -> Res<Request> {
+        Request::from_arguments(self.from, self.to, self.r#type, self.search, self.exclude)
+    }
+}
+
+/// Arguments of the duplicate detection
+#[derive(Args)]
+struct DuplicateArguments {
+    /// Maximum number of days between possible duplicates
+    #[arg(long, default_value_t = 3, value_name = "DAYS")]
+    duplicate_window: u64,
+
+    /// Allowed relative difference of the amounts in the base currency (0.02 = 2%)
+    #[arg(long, default_value_t = 0.02, value_parser = parse_confidence)]
+    duplicate_tolerance: f64,
+
+    /// Minimum score (0 to 1) to report a possible duplicate
+    #[arg(long, default_value_t = 0.78, value_parser = parse_confidence)]
+    duplicate_threshold: f64,
+}
+
+impl DuplicateArguments {
+    fn into_options(self) -> DuplicateCheckOptions {
+        DuplicateCheckOptions {
+            window_days: self.duplicate_window,
+            amount_tolerance: self.duplicate_tolerance,
+            threshold: self.duplicate_threshold,
+        }
+    }
+}
+
+fn parse_confidence(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+        _ => Err(format!("'{}' is not a number between 0 and 1", input)),
+    }
+}
+
+fn load_category_mapping(path: Option<PathBuf>) -> Res<CategoryMapping> {
+    match path {
+        Some(path) => CategoryMapping::load(path),
+        None => Ok(CategoryMapping::default()),
+    }
+}
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
@@ -106,42 +442,102 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     match cli.command {
         Some(Commands::Analyze {
             input,
-            to,
-            search,
-            from,
-            exclude,
-            r#type,
+            filter,
+            report_currency,
             verbosity,
         }) => {
             commands::analyze::analyze(
                 &mut printer,
-                base_currency,
+                report_currency.unwrap_or(base_currency),
                 &input,
-                from,
-                to,
-                search,
-                exclude,
-                r#type,
+                filter.into_request()?,
                 Verbosity::from_int(verbosity),
             )
             .await?
         }
 
-        Some(Commands::Import {
+        Some(Commands::Export {
             input,
+            filter,
+            format,
             output,
+            date_format,
+            category_map,
+            funding_account,
+            columns,
+            delimiter,
+            decimal_separator,
+        }) => {
+            let options = ExportOptions {
+                date_format,
+                category_mapping: load_category_mapping(category_map)?,
+                funding_account,
+                csv: CsvOptions {
+                    columns,
+                    delimiter,
+                    decimal_separator,
+                },
+            };
+            commands::export::export(
+                &mut printer,
+                &input,
+                filter.into_request()?,
+                format,
+                output.as_ref(),
+                options,
+            )
+            .await?
+        }
+
+        Some(Commands::Import {
+            command: Some(command),
+            ..
+        }) => match command {
+            ImportCommands::Batches { input } => {
+                commands::import::batches(&mut printer, &input).await?
+            }
+            ImportCommands::Rollback { batch, output } => {
+                commands::import::rollback(&mut printer, batch, &output).await?
+            }
+        },
+
+        Some(Commands::Import {
+            command: None,
+            inputs,
+            output,
+            format,
+            profile,
+            date_format,
+            category_map,
+            rules,
+            auto_assign_threshold,
+            duplicates,
+            on_duplicate,
             no_interaction,
+            dry_run,
+            report,
+            max_errors,
             verbosity,
         }) => {
             commands::import::import(
                 &mut printer,
                 base_currency,
-                &input,
-                &output,
+                &inputs,
+                &output.expect("Database is required"),
+                format,
+                profile,
+                date_format,
+                load_category_mapping(category_map)?,
+                rules.as_ref(),
+                auto_assign_threshold,
+                duplicates.into_options(),
+                on_duplicate,
                 no_interaction,
+                dry_run.then_some(report),
+                max_errors.map(|max| max as usize),
                 Verbosity::from_int(verbosity),
             )
-            .await?
+            .await?;
         }
 
         Some(Commands::Wizard {
@@ -149,6 +545,114 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             verbosity: _,
         }) => commands::wizard::wizard(&mut printer, base_currency, &output).await?,
 
+        Some(Commands::Convert {
+            amount,
+            currency,
+            to,
+            date,
+            file,
+        }) => {
+            commands::convert::convert(
+                &mut printer,
+                Amount::new(amount, currency),
+                to.unwrap_or(base_currency),
+                date,
+                file.as_ref(),
+            )
+            .await?
+        }
+
+        Some(Commands::Recategorize {
+            input,
+            filter,
+            rules,
+            dry_run,
+        }) => {
+            commands::recategorize::recategorize(
+                &mut printer,
+                base_currency,
+                &input,
+                filter.into_request()?,
+                rules.as_ref(),
+                dry_run,
+            )
+            .await?
+        }
+
+        Some(Commands::Watch {
+            directory,
+            output,
+            log,
+            profile,
+            category_map,
+            rules,
+            auto_assign_threshold,
+            duplicates,
+            on_duplicate,
+        }) => {
+            let options = WatchOptions {
+                profile,
+                category_mapping: load_category_mapping(category_map)?,
+                rules_file: rules,
+                auto_assign_threshold,
+                duplicate_options: duplicates.into_options(),
+                on_duplicate,
+            };
+            commands::watch::watch(
+                &mut printer,
+                base_currency,
+                &directory,
+                &output,
+                log.as_ref(),
+                options,
+            )
+            .await?
+        }
+
+        Some(Commands::Dedupe {
+            input,
+            filter,
+            duplicates,
+            report,
+        }) => {
+            commands::dedupe::dedupe(
+                &mut printer,
+                base_currency,
+                &input,
+                filter.into_request()?,
+                duplicates.into_options(),
+                report,
+            )
+            .await?
+        }
+
+        Some(Commands::Rules { command }) => match command {
+            RulesCommands::List { input } => commands::rules::list(&mut printer, &input).await?,
+            RulesCommands::Load { rules, output } => {
+                commands::rules::load(&mut printer, &rules, &output).await?
+            }
+            RulesCommands::Test {
+                input,
+                amount,
+                currency,
+                date,
+                note,
+                rules,
+            } => {
+                let currency = currency.unwrap_or(base_currency.clone());
+                commands::rules::test(
+                    &mut printer,
+                    base_currency,
+                    &input,
+                    rules.as_ref(),
+                    Amount::new(amount, currency),
+                    date,
+                    note,
+                )
+                .await?
+            }
+        },
+
         Some(Commands::ShowTypes {}) => commands::show_types::show_types(&mut printer),
         None => {}
     }
@@ -13,6 +13,10 @@ impl ExchangeRateRepository {
         Ok(Self { database })
     }
 
+    pub fn from_database(database: Database) -> Self {
+        Self { database }
+    }
+
     pub async fn fetch_all(&self) -> Result<Vec<ExchangeRate>, Error> {
         Ok(sqlx::query_as("SELECT * FROM exchange_rates;")
             .fetch_all(&self.database.pool)
@@ -0,0 +1,170 @@
+use super::Database;
+use crate::{error::Error, transaction::Transaction};
+use chrono::{Local, NaiveDateTime};
+use std::path::Path;
+
+/// One run of `import`, every transaction it inserted references it through `batch_id`
+#[derive(Clone, Debug, PartialEq, sqlx::FromRow)]
+pub struct ImportBatch {
+    pub uid: i64,
+    pub source: String,
+    /// SHA-256 of the imported file
+    pub content_hash: String,
+    pub importer: String,
+    pub imported_at: NaiveDateTime,
+    pub row_count: i64,
+}
+
+impl ImportBatch {
+    /// Describe a batch that has not been stored yet
+    pub fn new(source: String, content_hash: String, importer: String) -> Self {
+        Self {
+            uid: 0,
+            source,
+            content_hash,
+            importer,
+            imported_at: Local::now().naive_local(),
+            row_count: 0,
+        }
+    }
+}
+
+/// Rows an import batch writes
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchChanges {
+    pub inserted: Vec<Transaction>,
+    /// Stored transactions whose values are overwritten
+    pub replaced: Vec<Transaction>,
+    /// Stored transactions whose type, note, tags and external ID are updated
+    pub merged: Vec<Transaction>,
+}
+
+impl BatchChanges {
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.replaced.is_empty() && self.merged.is_empty()
+    }
+}
+
+/// Outcome of rolling back an import batch
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rollback {
+    /// Transactions the batch inserted
+    pub removed: u64,
+    /// Transactions the batch replaced, merged into or deleted, set back to their previous values
+    pub restored: u64,
+}
+
+pub struct ImportBatchRepository {
+    database: Database,
+}
+
+impl ImportBatchRepository {
+    pub async fn new(path: &Path) -> Result<Self, Error> {
+        let database = Database::new(path).await?;
+
+        Ok(Self { database })
+    }
+
+    pub async fn fetch_all(&self) -> Result<Vec<ImportBatch>, Error> {
+        Ok(sqlx::query_as("SELECT * FROM import_batches ORDER BY uid;")
+            .fetch_all(&self.database.pool)
+            .await?)
+    }
+
+    /// Return the batches that imported a file with the same content
+    pub async fn find_by_hash(&self, content_hash: &str) -> Result<Vec<ImportBatch>, Error> {
+        Ok(
+            sqlx::query_as("SELECT * FROM import_batches WHERE content_hash = ? ORDER BY uid;")
+                .bind(content_hash)
+                .fetch_all(&self.database.pool)
+                .await?,
+        )
+    }
+
+    /// Remove the batch and all transactions it inserted, and restore the transactions it
+    /// replaced, merged into or deleted
+    ///
+    /// This is refused while a later batch, e.g. of `dedupe`, changed or deleted one of these
+    /// transactions, because restoring them would drop its changes
+    pub async fn rollback(&self, uid: i64) -> Result<Rollback, Error> {
+        let mut db_transaction = self.database.pool.begin().await?;
+        let later_batches: Vec<i64> = sqlx::query_scalar(
+            r#"
+SELECT DISTINCT batch_id FROM batch_changes
+WHERE batch_id > ?1 AND (
+    transaction_batch_id = ?1
+    OR transaction_id IN (SELECT uid FROM transactions WHERE batch_id = ?1)
+    OR transaction_id IN (SELECT transaction_id FROM batch_changes WHERE batch_id = ?1)
+)
+ORDER BY batch_id;
+        "#,
+        )
+        .bind(uid)
+        .fetch_all(&mut *db_transaction)
+        .await?;
+        if !later_batches.is_empty() {
+            let later_batches: Vec<String> = later_batches
+                .iter()
+                .map(|batch| format!("#{}", batch))
+                .collect();
+            return Err(Error::Persistence(format!(
+                "Transactions of import batch #{} were changed later by {}, roll back the later \
+                 batches first",
+                uid,
+                later_batches.join(", ")
+            )));
+        }
+
+        let updated_transactions = sqlx::query(
+            r#"
+UPDATE transactions
+SET date = c.date, currency = c.currency, amount = c.amount, type = c.type, note = c.note,
+    external_id = c.external_id, base_amount = c.base_amount, tags = c.tags
+FROM batch_changes AS c
+WHERE c.batch_id = ? AND NOT c.deleted AND c.transaction_id = transactions.uid;
+        "#,
+        )
+        .bind(uid)
+        .execute(&mut *db_transaction)
+        .await?
+        .rows_affected();
+        // Deleted transactions get their ID and batch back
+        let inserted_transactions = sqlx::query(
+            r#"
+INSERT INTO transactions ( uid, date, currency, amount, type, note, external_id, base_amount, tags, batch_id )
+SELECT transaction_id, date, currency, amount, type, note, external_id, base_amount, tags, transaction_batch_id
+FROM batch_changes WHERE batch_id = ? AND deleted;
+        "#,
+        )
+        .bind(uid)
+        .execute(&mut *db_transaction)
+        .await?
+        .rows_affected();
+        sqlx::query("DELETE FROM batch_changes WHERE batch_id = ?;")
+            .bind(uid)
+            .execute(&mut *db_transaction)
+            .await?;
+        let removed_transactions = sqlx::query("DELETE FROM transactions WHERE batch_id = ?;")
+            .bind(uid)
+            .execute(&mut *db_transaction)
+            .await?
+            .rows_affected();
+        let removed_batches = sqlx::query("DELETE FROM import_batches WHERE uid = ?;")
+            .bind(uid)
+            .execute(&mut *db_transaction)
+            .await?
+            .rows_affected();
+        if removed_batches == 0 {
+            return Err(Error::Persistence(format!(
+                "Import batch #{} not found",
+                uid
+            )));
+        }
+        db_transaction.commit().await?;
+
+        Ok(Rollback {
+            removed: removed_transactions,
+            restored: updated_transactions + inserted_transactions,
+        })
+    }
+}
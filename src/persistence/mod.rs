@@ -1,12 +1,53 @@
 mod exchange_rate_repository;
+mod import_batch_repository;
+mod rule_repository;
 mod transaction_repository;
 
 use crate::error::Error;
 pub use exchange_rate_repository::ExchangeRateRepository;
-use sqlx::SqlitePool;
+pub use import_batch_repository::{BatchChanges, ImportBatch, ImportBatchRepository};
+pub use rule_repository::RuleRepository;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{Connection, SqliteConnection, SqlitePool};
 use std::path::Path;
 pub use transaction_repository::TransactionRepository;
 
+/// Migrations that change existing tables
+///
+/// They are applied once, in order, and tracked through SQLite's `user_version`
+const VERSIONED_MIGRATIONS: [(i64, &str, &str); 6] = [
+    (
+        3,
+        "add-external-id",
+        include_str!("../../migrations/03-add-external-id.sql"),
+    ),
+    (
+        4,
+        "add-base-amount",
+        include_str!("../../migrations/04-add-base-amount.sql"),
+    ),
+    (
+        5,
+        "add-tags-and-rules",
+        include_str!("../../migrations/05-add-tags-and-rules.sql"),
+    ),
+    (
+        6,
+        "add-import-batches",
+        include_str!("../../migrations/06-add-import-batches.sql"),
+    ),
+    (
+        7,
+        "add-batch-changes",
+        include_str!("../../migrations/07-add-batch-changes.sql"),
+    ),
+    (
+        8,
+        "keep-deleted-batch-rows",
+        include_str!("../../migrations/08-keep-deleted-batch-rows.sql"),
+    ),
+];
+
 pub struct Database {
     pub pool: SqlitePool,
 }
@@ -27,14 +68,33 @@ impl Database {
             }
         };
 
-        Self::prepare_database(&pool, path).await?;
+        Self::prepare_database(&mut *pool.acquire().await?, path).await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Create a temporary database that only contains the prefilled data
+    pub async fn in_memory() -> Result<Self, Error> {
+        // Every connection would open its own in-memory database, so keep exactly one alive
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+
+        Self::prepare_database(&mut *pool.acquire().await?, Path::new(":memory:")).await?;
 
         Ok(Self { pool })
     }
 
-    async fn prepare_database(pool: &SqlitePool, path: &Path) -> Result<(), Error> {
+    /// Run the migrations on a single connection
+    ///
+    /// SQLite connections that were opened before a migration may still prepare statements with
+    /// the old schema, which breaks `SELECT *` queries
+    async fn prepare_database(connection: &mut SqliteConnection, path: &Path) -> Result<(), Error> {
         sqlx::query(include_str!("../../migrations/01-create-tables.sql"))
-            .execute(pool)
+            .execute(&mut *connection)
             .await
             .map_err(|e| {
                 // Check for an "attempt to write a readonly database"-error
@@ -53,12 +113,80 @@ impl Database {
             })?;
 
         sqlx::query(include_str!("../../migrations/02-prefill-tables.sql"))
-            .execute(pool)
+            .execute(&mut *connection)
             .await
             .map_err(|e| {
                 Error::Persistence(format!("Error during database migration #prefill: {}", e))
             })?;
 
+        Self::run_migrations(connection, &VERSIONED_MIGRATIONS).await
+    }
+
+    /// Apply the migrations newer than the database's `user_version`
+    ///
+    /// Each migration and its version bump run in one transaction, so a failing migration leaves
+    /// the database at the previous version
+    async fn run_migrations(
+        connection: &mut SqliteConnection,
+        migrations: &[(i64, &str, &str)],
+    ) -> Result<(), Error> {
+        let current_version: i64 = sqlx::query_scalar("PRAGMA user_version;")
+            .fetch_one(&mut *connection)
+            .await?;
+        for (version, name, migration) in migrations {
+            if *version <= current_version {
+                continue;
+            }
+
+            let mut db_transaction = connection.begin().await?;
+            sqlx::query(migration)
+                .execute(&mut *db_transaction)
+                .await
+                .map_err(|e| {
+                    Error::Persistence(format!("Error during database migration #{}: {}", name, e))
+                })?;
+            sqlx::query(&format!("PRAGMA user_version = {};", version))
+                .execute(&mut *db_transaction)
+                .await?;
+            db_transaction.commit().await?;
+        }
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn user_version(connection: &mut SqliteConnection) -> i64 {
+        sqlx::query_scalar("PRAGMA user_version;")
+            .fetch_one(connection)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn failing_migration_is_rolled_back() {
+        let database = Database::in_memory().await.unwrap();
+        let mut connection = database.pool.acquire().await.unwrap();
+        let latest = VERSIONED_MIGRATIONS[VERSIONED_MIGRATIONS.len() - 1].0;
+        assert_eq!(user_version(&mut connection).await, latest);
+
+        let migrations = [(
+            latest + 1,
+            "broken",
+            "CREATE TABLE broken (id INTEGER); INSERT INTO missing VALUES (1);",
+        )];
+        let result = Database::run_migrations(&mut connection, &migrations).await;
+        assert!(result.is_err());
+
+        assert_eq!(user_version(&mut connection).await, latest);
+        let tables: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE name = 'broken'")
+                .fetch_one(&mut *connection)
+                .await
+                .unwrap();
+        assert_eq!(tables, 0);
+    }
+}
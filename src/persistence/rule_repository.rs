@@ -0,0 +1,53 @@
+use super::Database;
+use crate::{error::Error, rules::Rule};
+use sqlx::Row;
+use std::path::Path;
+
+/// Rules are stored as JSON, so new conditions don't need a migration
+pub struct RuleRepository {
+    database: Database,
+}
+
+impl RuleRepository {
+    pub async fn new(path: &Path) -> Result<Self, Error> {
+        let database = Database::new(path).await?;
+
+        Ok(Self { database })
+    }
+
+    /// Return the rules in the order they are applied
+    pub async fn fetch_all(&self) -> Result<Vec<Rule>, Error> {
+        let rows = sqlx::query("SELECT definition FROM rules ORDER BY position;")
+            .fetch_all(&self.database.pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let definition: String = row.try_get("definition")?;
+                serde_json::from_str(&definition)
+                    .map_err(|e| Error::Persistence(format!("Could not read stored rule: {}", e)))
+            })
+            .collect()
+    }
+
+    /// Replace all stored rules
+    pub async fn replace_all(&self, rules: &[Rule]) -> Result<(), Error> {
+        let mut db_transaction = self.database.pool.begin().await?;
+        sqlx::query("DELETE FROM rules;")
+            .execute(&mut *db_transaction)
+            .await?;
+
+        for (position, rule) in rules.iter().enumerate() {
+            let definition = serde_json::to_string(rule)
+                .map_err(|e| Error::Persistence(format!("Could not serialize rule: {}", e)))?;
+            sqlx::query("INSERT INTO rules ( position, definition ) VALUES ( ?, ? );")
+                .bind(position as i64)
+                .bind(definition)
+                .execute(&mut *db_transaction)
+                .await?;
+        }
+        db_transaction.commit().await?;
+
+        Ok(())
+    }
+}
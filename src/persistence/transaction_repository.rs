@@ -1,13 +1,14 @@
-use super::{Database, ExchangeRateRepository};
+use super::{BatchChanges, Database, ExchangeRateRepository, ImportBatch};
 use crate::{
     currency::{
         amount_converter::AmountConverter, exchange_rate_provider::ExchangeRateProvider, Currency,
     },
     error::Error,
     filter::Request,
-    transaction::Transaction,
+    transaction::{join_tags, Transaction},
 };
 use chrono::{NaiveDate, Utc};
+use sqlx::SqliteExecutor;
 use std::path::Path;
 
 pub struct TransactionRepository {
@@ -29,31 +30,219 @@ impl TransactionRepository {
     }
 
     pub async fn add(&self, transaction: &Transaction) -> Result<i64, Error> {
+        Self::insert(&self.database.pool, transaction, None).await
+    }
+
+    /// Store each batch and its changes in one database transaction
+    ///
+    /// The previous values of replaced and merged rows are kept with the batch, so rolling it
+    /// back can restore them. Nothing is written if one of the statements fails
+    pub async fn add_batches(
+        &self,
+        batches: Vec<(ImportBatch, BatchChanges)>,
+    ) -> Result<Vec<ImportBatch>, Error> {
+        let mut stored_batches = vec![];
+
+        let mut db_transaction = self.database.pool.begin().await?;
+        for (mut batch, changes) in batches {
+            batch.row_count = changes.inserted.len() as i64;
+            batch.uid = Self::insert_batch(&mut *db_transaction, &batch).await?;
+
+            for transaction in &changes.inserted {
+                Self::insert(&mut *db_transaction, transaction, Some(batch.uid)).await?;
+            }
+            for transaction in &changes.replaced {
+                Self::keep_previous(&mut *db_transaction, transaction, batch.uid, false).await?;
+                Self::replace(&mut *db_transaction, transaction).await?;
+            }
+            for transaction in &changes.merged {
+                Self::keep_previous(&mut *db_transaction, transaction, batch.uid, false).await?;
+                Self::update_details(&mut *db_transaction, transaction).await?;
+            }
+            stored_batches.push(batch);
+        }
+        db_transaction.commit().await?;
+
+        Ok(stored_batches)
+    }
+
+    /// Return the ID of the new batch
+    async fn insert_batch<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        batch: &ImportBatch,
+    ) -> Result<i64, Error> {
+        Ok(sqlx::query(
+            r#"
+INSERT INTO import_batches ( source, content_hash, importer, imported_at, row_count )
+VALUES ( ?, ?, ?, ?, ? )
+        "#,
+        )
+        .bind(&batch.source)
+        .bind(&batch.content_hash)
+        .bind(&batch.importer)
+        .bind(batch.imported_at)
+        .bind(batch.row_count)
+        .execute(executor)
+        .await?
+        .last_insert_rowid())
+    }
+
+    /// Copy the stored values of a transaction to the changes of the batch that is about to
+    /// overwrite or delete them
+    async fn keep_previous<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        transaction: &Transaction,
+        batch_id: i64,
+        deleted: bool,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+INSERT INTO batch_changes ( batch_id, transaction_id, date, currency, amount, type, note, external_id, base_amount, tags, deleted, transaction_batch_id )
+SELECT ?, uid, date, currency, amount, type, note, external_id, base_amount, tags, ?, batch_id
+FROM transactions WHERE uid = ?;
+        "#,
+        )
+        .bind(batch_id)
+        .bind(deleted)
+        .bind(stored_uid(transaction)?)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        transaction: &Transaction,
+        batch_id: Option<i64>,
+    ) -> Result<i64, Error> {
         let date = transaction.date();
         let currency = transaction.amount().currency().iso;
         let value = transaction.amount().value();
         let transaction_type = transaction.transaction_type();
-        let note = transaction.note();
+        let note = transaction.note().unwrap_or_default();
+        let external_id = transaction.external_id();
+        // Only a base amount that came with the transaction is stored, others are calculated
+        // from the exchange rates when fetching
+        let base_amount = transaction
+            .base_amount
+            .as_ref()
+            .filter(|_| transaction.amount.currency != Currency::base())
+            .map(|base_amount| base_amount.value);
+        let tags = join_tags(&transaction.tags);
 
         // Insert the spending, then obtain the ID of this row
         let id = sqlx::query!(
             r#"
-INSERT INTO transactions ( date, currency, amount, type, note )
-VALUES ( ?1, ?2, ?3, ?4, ?5 )
+INSERT INTO transactions ( date, currency, amount, type, note, external_id, base_amount, tags, batch_id )
+VALUES ( ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9 )
         "#,
             date,
             currency,
             value,
             transaction_type,
             note,
+            external_id,
+            base_amount,
+            tags,
+            batch_id,
         )
-        .execute(&self.database.pool)
+        .execute(executor)
         .await?
         .last_insert_rowid();
 
         Ok(id)
     }
 
+    async fn update_details<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        transaction: &Transaction,
+    ) -> Result<(), Error> {
+        let uid = stored_uid(transaction)?;
+
+        sqlx::query(
+            "UPDATE transactions SET type = ?, note = ?, tags = ?, external_id = ? WHERE uid = ?;",
+        )
+        .bind(transaction.transaction_type)
+        .bind(transaction.note.clone().unwrap_or_default())
+        .bind(join_tags(&transaction.tags))
+        .bind(transaction.external_id.clone())
+        .bind(uid)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Overwrite all values of a stored transaction
+    async fn replace<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        transaction: &Transaction,
+    ) -> Result<(), Error> {
+        let uid = stored_uid(transaction)?;
+        let base_amount = transaction
+            .base_amount
+            .as_ref()
+            .filter(|_| transaction.amount.currency != Currency::base())
+            .map(|base_amount| base_amount.value);
+
+        sqlx::query(
+            r#"
+UPDATE transactions
+SET date = ?, currency = ?, amount = ?, type = ?, note = ?, external_id = ?, base_amount = ?, tags = ?
+WHERE uid = ?;
+        "#,
+        )
+        .bind(transaction.date)
+        .bind(transaction.amount.currency.iso.clone())
+        .bind(transaction.amount.value)
+        .bind(transaction.transaction_type)
+        .bind(transaction.note.clone().unwrap_or_default())
+        .bind(transaction.external_id.clone())
+        .bind(base_amount)
+        .bind(join_tags(&transaction.tags))
+        .bind(uid)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store the type, note, tags and external ID of a transaction that was loaded from the
+    /// database
+    pub async fn update(&self, transaction: &Transaction) -> Result<(), Error> {
+        Self::update_details(&self.database.pool, transaction).await
+    }
+
+    /// Store `merged` and delete `deleted` in one database transaction
+    ///
+    /// The changes are recorded as a batch of their own, so `import rollback` can undo them and
+    /// refuses to roll back an import whose rows they changed
+    pub async fn resolve_duplicates(
+        &self,
+        merged: Option<&Transaction>,
+        deleted: &[Transaction],
+    ) -> Result<ImportBatch, Error> {
+        let mut batch = ImportBatch::new("dedupe".to_owned(), String::new(), "dedupe".to_owned());
+
+        let mut db_transaction = self.database.pool.begin().await?;
+        batch.uid = Self::insert_batch(&mut *db_transaction, &batch).await?;
+        if let Some(merged) = merged {
+            Self::keep_previous(&mut *db_transaction, merged, batch.uid, false).await?;
+            Self::update_details(&mut *db_transaction, merged).await?;
+        }
+        for transaction in deleted {
+            Self::keep_previous(&mut *db_transaction, transaction, batch.uid, true).await?;
+            sqlx::query("DELETE FROM transactions WHERE uid = ?;")
+                .bind(stored_uid(transaction)?)
+                .execute(&mut *db_transaction)
+                .await?;
+        }
+        db_transaction.commit().await?;
+
+        Ok(batch)
+    }
+
     pub async fn fetch_all(&self) -> Result<Vec<Transaction>, Error> {
         let transactions: Vec<Transaction> = sqlx::query_as(r#"SELECT * FROM transactions;"#)
             .fetch_all(&self.database.pool)
@@ -68,6 +257,20 @@ VALUES ( ?1, ?2, ?3, ?4, ?5 )
     pub async fn fetch_with_request(
         &self,
         filter_request: Request,
+    ) -> Result<Vec<Transaction>, Error> {
+        Ok(self
+            .fetch_stored_with_request(filter_request)
+            .await?
+            .into_iter()
+            .map(|i| self.prepare_base_amount(i))
+            .collect())
+    }
+
+    /// Like `fetch_with_request`, but base amounts are only set when they were stored with
+    /// the transaction
+    pub async fn fetch_stored_with_request(
+        &self,
+        filter_request: Request,
     ) -> Result<Vec<Transaction>, Error> {
         let from = filter_request
             .from
@@ -101,18 +304,20 @@ VALUES ( ?1, ?2, ?3, ?4, ?5 )
             .bind(exclude)
         };
 
-        let transactions: Vec<Transaction> = query.fetch_all(&self.database.pool).await?;
+        Ok(query.fetch_all(&self.database.pool).await?)
+    }
 
-        Ok(transactions
-            .into_iter()
-            .map(|i| self.prepare_base_amount(i))
-            .collect())
+    pub fn exchange_rate_provider(&self) -> &ExchangeRateProvider {
+        &self.exchange_rate_provider
     }
 
     fn prepare_base_amount(&self, transaction: Transaction) -> Transaction {
         if transaction.amount.currency == Currency::base() {
             return transaction.with_base_amount(transaction.amount.clone());
         }
+        if transaction.base_amount.is_some() {
+            return transaction;
+        }
 
         let exchange_rate = self.exchange_rate_provider.find_exchange_rate(&transaction);
 
@@ -122,3 +327,259 @@ VALUES ( ?1, ?2, ?3, ?4, ?5 )
         }
     }
 }
+
+fn stored_uid(transaction: &Transaction) -> Result<i64, Error> {
+    transaction.uid.ok_or_else(|| {
+        Error::Persistence("Cannot update a transaction that was not stored".to_owned())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        persistence::ImportBatchRepository,
+        transaction::{amount::Amount, transaction_type::TransactionType},
+    };
+    use std::path::PathBuf;
+
+    fn database_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("budgeteer-{}-test.sqlite", name));
+        let _ = std::fs::remove_file(&path);
+
+        path
+    }
+
+    fn transaction(note: Option<&str>) -> Transaction {
+        Transaction::new(
+            NaiveDate::from_ymd_opt(2024, 2, 3).unwrap(),
+            Amount::new(-12.5, Currency::base()),
+            None,
+            TransactionType::Eat,
+            note.map(str::to_owned),
+        )
+    }
+
+    fn inserted(transactions: Vec<Transaction>) -> BatchChanges {
+        BatchChanges {
+            inserted: transactions,
+            ..BatchChanges::default()
+        }
+    }
+
+    fn notes(transactions: Vec<Transaction>) -> Vec<Option<String>> {
+        transactions
+            .into_iter()
+            .map(|transaction| transaction.note)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn add_batches_without_note() {
+        let path = database_path("add-batches");
+        let repository = TransactionRepository::new(&path).await.unwrap();
+        let batch = ImportBatch::new("a.json".to_owned(), "hash".to_owned(), "JSON".to_owned());
+
+        let batches = repository
+            .add_batches(vec![(
+                batch,
+                inserted(vec![transaction(None), transaction(Some("Lunch"))]),
+            )])
+            .await
+            .unwrap();
+
+        assert_eq!(batches[0].row_count, 2);
+        let notes: Vec<Option<String>> = repository
+            .fetch_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|transaction| transaction.note)
+            .collect();
+        assert_eq!(notes, vec![Some(String::new()), Some("Lunch".to_owned())]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rollback_removes_only_the_batch() {
+        let path = database_path("rollback");
+        let repository = TransactionRepository::new(&path).await.unwrap();
+        let batch =
+            |source: &str| ImportBatch::new(source.to_owned(), source.to_owned(), "CSV".to_owned());
+        let batches = repository
+            .add_batches(vec![
+                (batch("a.csv"), inserted(vec![transaction(Some("First"))])),
+                (
+                    batch("b.csv"),
+                    inserted(vec![transaction(Some("Second")), transaction(None)]),
+                ),
+            ])
+            .await
+            .unwrap();
+        repository.add(&transaction(Some("Manual"))).await.unwrap();
+
+        let batch_repository = ImportBatchRepository::new(&path).await.unwrap();
+        assert_eq!(
+            batch_repository
+                .rollback(batches[1].uid)
+                .await
+                .unwrap()
+                .removed,
+            2
+        );
+        assert!(batch_repository.rollback(batches[1].uid).await.is_err());
+
+        let notes: Vec<Option<String>> = repository
+            .fetch_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|transaction| transaction.note)
+            .collect();
+        assert_eq!(
+            notes,
+            vec![Some("First".to_owned()), Some("Manual".to_owned())]
+        );
+        assert_eq!(batch_repository.fetch_all().await.unwrap().len(), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rollback_restores_replaced_and_merged() {
+        let path = database_path("rollback-restore");
+        let repository = TransactionRepository::new(&path).await.unwrap();
+        let batch =
+            |source: &str| ImportBatch::new(source.to_owned(), source.to_owned(), "CSV".to_owned());
+        repository
+            .add_batches(vec![(
+                batch("a.csv"),
+                inserted(vec![
+                    transaction(Some("First")),
+                    transaction(Some("Second")),
+                ]),
+            )])
+            .await
+            .unwrap();
+        let stored = repository.fetch_all().await.unwrap();
+
+        let mut replaced = stored[0].clone();
+        replaced.amount.value = -20.0;
+        replaced.note = Some("Replaced".to_owned());
+        let mut merged = stored[1].clone();
+        merged.note = Some("Merged".to_owned());
+        let batches = repository
+            .add_batches(vec![(
+                batch("b.csv"),
+                BatchChanges {
+                    replaced: vec![replaced],
+                    merged: vec![merged],
+                    ..BatchChanges::default()
+                },
+            )])
+            .await
+            .unwrap();
+        assert_eq!(batches[0].row_count, 0);
+        assert_eq!(
+            notes(repository.fetch_all().await.unwrap()),
+            vec![Some("Replaced".to_owned()), Some("Merged".to_owned())]
+        );
+
+        let batch_repository = ImportBatchRepository::new(&path).await.unwrap();
+        let rollback = batch_repository.rollback(batches[0].uid).await.unwrap();
+        assert_eq!(rollback.removed, 0);
+        assert_eq!(rollback.restored, 2);
+        let restored = repository.fetch_all().await.unwrap();
+        assert_eq!(restored, stored);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rollback_refused_after_later_changes() {
+        let path = database_path("rollback-refused");
+        let repository = TransactionRepository::new(&path).await.unwrap();
+        let batch =
+            |source: &str| ImportBatch::new(source.to_owned(), source.to_owned(), "CSV".to_owned());
+        let first = repository
+            .add_batches(vec![(
+                batch("a.csv"),
+                inserted(vec![transaction(Some("First"))]),
+            )])
+            .await
+            .unwrap();
+        let mut replaced = repository.fetch_all().await.unwrap().remove(0);
+        replaced.note = Some("Replaced".to_owned());
+        let second = repository
+            .add_batches(vec![(
+                batch("b.csv"),
+                BatchChanges {
+                    replaced: vec![replaced],
+                    ..BatchChanges::default()
+                },
+            )])
+            .await
+            .unwrap();
+
+        let batch_repository = ImportBatchRepository::new(&path).await.unwrap();
+        assert!(batch_repository.rollback(first[0].uid).await.is_err());
+        assert_eq!(
+            notes(repository.fetch_all().await.unwrap()),
+            vec![Some("Replaced".to_owned())]
+        );
+
+        batch_repository.rollback(second[0].uid).await.unwrap();
+        batch_repository.rollback(first[0].uid).await.unwrap();
+        assert!(repository.fetch_all().await.unwrap().is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rollback_after_resolve_duplicates() {
+        let path = database_path("resolve-duplicates");
+        let repository = TransactionRepository::new(&path).await.unwrap();
+        let imported = repository
+            .add_batches(vec![(
+                ImportBatch::new("a.csv".to_owned(), "a".to_owned(), "CSV".to_owned()),
+                inserted(vec![
+                    transaction(Some("Imported")),
+                    transaction(Some("Copy")),
+                ]),
+            )])
+            .await
+            .unwrap();
+        repository.add(&transaction(Some("Manual"))).await.unwrap();
+        let stored = repository.fetch_all().await.unwrap();
+
+        // Keep the imported row, delete the copy from the same import and the manual entry
+        let mut merged = stored[0].clone();
+        merged.note = Some("Imported / Manual".to_owned());
+        let dedupe = repository
+            .resolve_duplicates(Some(&merged), &stored[1..])
+            .await
+            .unwrap();
+        assert_eq!(
+            notes(repository.fetch_all().await.unwrap()),
+            vec![Some("Imported / Manual".to_owned())]
+        );
+
+        let batch_repository = ImportBatchRepository::new(&path).await.unwrap();
+        assert!(batch_repository.rollback(imported[0].uid).await.is_err());
+        let rollback = batch_repository.rollback(dedupe.uid).await.unwrap();
+        assert_eq!(rollback.restored, 3);
+        assert_eq!(repository.fetch_all().await.unwrap(), stored);
+
+        // The deleted copy belongs to its import again
+        assert_eq!(
+            batch_repository
+                .rollback(imported[0].uid)
+                .await
+                .unwrap()
+                .removed,
+            2
+        );
+        assert_eq!(
+            notes(repository.fetch_all().await.unwrap()),
+            vec![Some("Manual".to_owned())]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -54,8 +54,8 @@ impl Printer {
     fn print_type_sum(&mut self, base_currency: &Currency, transactions: &[Transaction]) {
         // Skip currencies without any Transaction
         let currencies_to_output: Vec<Currency> = currency_data::all()
-            .into_iter()
-            .filter_map(|(_, currency)| {
+            .into_values()
+            .filter_map(|currency| {
                 if contains_transaction_in_currency(transactions, &currency) {
                     Some(currency)
                 } else {
@@ -170,6 +170,11 @@ impl PrinterTrait for Printer {
 
         let transaction_type = transaction.transaction_type();
         let date = transaction.date().format("%A %d.%m.%Y");
+        let tags = if transaction.tags.is_empty() {
+            "".to_owned()
+        } else {
+            format!("Tags        : {}\n", transaction.tags.join(", "))
+        };
 
         writeln!(
             self.output,
@@ -177,12 +182,13 @@ impl PrinterTrait for Printer {
 Betrag      : {}
 Typ         : {}
 Notiz       : {}
-"#,
+{}"#,
             style_for_type(transaction_type, "   ", false, true),
             date,
             amount_string,
             transaction_type,
             note,
+            tags,
         )
         .expect(STDOUT_WRITE_ERROR);
     }
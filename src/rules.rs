@@ -0,0 +1,314 @@
+use crate::{
+    currency::Currency,
+    error::{Error, Res},
+    transaction::{transaction_type::TransactionType, Transaction},
+};
+use chrono::{Datelike, Weekday};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs::File, io::BufReader, path::Path, str::FromStr};
+
+/// A rule assigns a type, a new note and tags to the transactions it matches
+///
+/// All conditions that are set must match. Amounts are compared in the transaction's currency,
+/// expenses are negative
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rule {
+    pub name: String,
+
+    /// Regular expression that must match the note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note_regex: Option<String>,
+    /// Text the note must contain (case-insensitive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note_contains: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub weekdays: Vec<Weekday>,
+
+    /// Type identifier or name to assign
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_type: Option<String>,
+    /// New note, `$1` or `${name}` refer to the groups of `note_regex`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_note: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub add_tags: Vec<String>,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut conditions = vec![];
+        if let Some(note_regex) = &self.note_regex {
+            conditions.push(format!("note ~ /{}/", note_regex));
+        }
+        if let Some(note_contains) = &self.note_contains {
+            conditions.push(format!("note contains '{}'", note_contains));
+        }
+        match (self.min_amount, self.max_amount) {
+            (Some(min), Some(max)) => conditions.push(format!("amount {} to {}", min, max)),
+            (Some(min), None) => conditions.push(format!("amount >= {}", min)),
+            (None, Some(max)) => conditions.push(format!("amount <= {}", max)),
+            (None, None) => {}
+        }
+        if let Some(currency) = &self.currency {
+            conditions.push(format!("currency {}", currency));
+        }
+        if !self.weekdays.is_empty() {
+            let weekdays: Vec<String> = self.weekdays.iter().map(|w| w.to_string()).collect();
+            conditions.push(format!("on {}", weekdays.join("/")));
+        }
+
+        let mut actions = vec![];
+        if let Some(set_type) = &self.set_type {
+            actions.push(format!("type {}", TransactionType::from_str(set_type)));
+        }
+        if let Some(set_note) = &self.set_note {
+            actions.push(format!("note '{}'", set_note));
+        }
+        if !self.add_tags.is_empty() {
+            actions.push(format!("tags {}", self.add_tags.join(", ")));
+        }
+
+        let conditions = if conditions.is_empty() {
+            "always".to_owned()
+        } else {
+            conditions.join(", ")
+        };
+        write!(f, "{}: {} → {}", self.name, conditions, actions.join(", "))
+    }
+}
+
+struct CompiledRule {
+    rule: Rule,
+    note_regex: Option<Regex>,
+    currency: Option<Currency>,
+    transaction_type: Option<TransactionType>,
+}
+
+impl CompiledRule {
+    fn new(rule: Rule) -> Res<Self> {
+        let invalid =
+            |message: String| Error::Argument(format!("Rule '{}': {}", rule.name, message));
+
+        let note_regex = match &rule.note_regex {
+            Some(pattern) => Some(
+                Regex::new(pattern)
+                    .map_err(|e| invalid(format!("Invalid regular expression: {}", e)))?,
+            ),
+            None => None,
+        };
+        let currency = match &rule.currency {
+            Some(currency) => Some(Currency::from_str(currency).map_err(|e| invalid(e.message()))?),
+            None => None,
+        };
+        let transaction_type = match &rule.set_type {
+            Some(raw_type) => match TransactionType::from_str(raw_type) {
+                TransactionType::Unknown if !raw_type.eq_ignore_ascii_case("U") => {
+                    return Err(invalid(format!("Unknown type '{}'", raw_type)))
+                }
+                transaction_type => Some(transaction_type),
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            rule,
+            note_regex,
+            currency,
+            transaction_type,
+        })
+    }
+
+    fn matches(&self, transaction: &Transaction) -> bool {
+        let note = transaction.note.as_deref().unwrap_or_default();
+        let value = transaction.amount.value;
+
+        self.note_regex.as_ref().is_none_or(|r| r.is_match(note))
+            && self
+                .rule
+                .note_contains
+                .as_ref()
+                .is_none_or(|text| note.to_lowercase().contains(&text.to_lowercase()))
+            && self.rule.min_amount.is_none_or(|min| value >= min)
+            && self.rule.max_amount.is_none_or(|max| value <= max)
+            && self
+                .currency
+                .as_ref()
+                .is_none_or(|c| &transaction.amount.currency == c)
+            && (self.rule.weekdays.is_empty()
+                || self.rule.weekdays.contains(&transaction.date.weekday()))
+    }
+
+    fn apply(&self, transaction: Transaction) -> Transaction {
+        let mut transaction = transaction;
+        if let Some(transaction_type) = self.transaction_type {
+            transaction.transaction_type = transaction_type;
+        }
+
+        if let Some(set_note) = &self.rule.set_note {
+            let note = transaction.note.clone().unwrap_or_default();
+            let new_note = match self.note_regex.as_ref().and_then(|r| r.captures(&note)) {
+                Some(captures) => {
+                    let mut expanded = String::new();
+                    captures.expand(set_note, &mut expanded);
+                    expanded
+                }
+                None => set_note.clone(),
+            };
+            transaction.note = Some(new_note);
+        }
+
+        for tag in &self.rule.add_tags {
+            if !transaction.tags.contains(tag) {
+                transaction.tags.push(tag.clone());
+            }
+        }
+
+        transaction
+    }
+}
+
+/// Ordered list of rules, the first matching rule is applied
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<Rule>) -> Res<Self> {
+        Ok(Self {
+            rules: rules
+                .into_iter()
+                .map(CompiledRule::new)
+                .collect::<Res<_>>()?,
+        })
+    }
+
+    /// Load a JSON array of rules
+    pub fn load<P: AsRef<Path>>(path: P) -> Res<Vec<Rule>> {
+        let file = File::open(&path)?;
+
+        serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+            Error::Argument(format!(
+                "Could not read rules file {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Return the position and the first rule that matches `transaction`
+    pub fn find_match(&self, transaction: &Transaction) -> Option<(usize, &Rule)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, compiled)| compiled.matches(transaction))
+            .map(|(index, compiled)| (index, &compiled.rule))
+    }
+
+    /// Apply the first matching rule, transactions without a match are returned unchanged
+    pub fn apply(&self, transaction: Transaction) -> Transaction {
+        match self
+            .rules
+            .iter()
+            .find(|compiled| compiled.matches(&transaction))
+        {
+            Some(compiled) => compiled.apply(transaction),
+            None => transaction,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::amount::Amount;
+    use chrono::NaiveDate;
+
+    fn rules() -> Vec<Rule> {
+        serde_json::from_str(
+            r#"[
+                {
+                    "name": "Weekend groceries",
+                    "note_regex": "(?i)^(migros|coop)\\b",
+                    "weekdays": ["Sat", "Sun"],
+                    "set_type": "E",
+                    "set_note": "Groceries at $1",
+                    "add_tags": ["weekend"]
+                },
+                {
+                    "name": "Small purchases",
+                    "note_contains": "shop",
+                    "min_amount": -20.0,
+                    "max_amount": 0.0,
+                    "currency": "CHF",
+                    "set_type": "Fun"
+                }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn transaction(date: NaiveDate, value: f64, currency: Currency, note: &str) -> Transaction {
+        Transaction::new(
+            date,
+            Amount::new(value, currency),
+            None,
+            TransactionType::Unknown,
+            Some(note.to_owned()),
+        )
+    }
+
+    #[test]
+    fn apply_first_matching_rule() {
+        let rule_set = RuleSet::new(rules()).unwrap();
+        let saturday = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+
+        let groceries =
+            rule_set.apply(transaction(saturday, -50.0, Currency::chf(), "MIGROS Bern"));
+        assert_eq!(groceries.transaction_type, TransactionType::Eat);
+        assert_eq!(groceries.note, Some("Groceries at MIGROS".to_owned()));
+        assert_eq!(groceries.tags, vec!["weekend".to_owned()]);
+
+        let weekday = transaction(monday, -50.0, Currency::chf(), "Migros Bern");
+        assert_eq!(rule_set.find_match(&weekday), None);
+
+        let shop = transaction(monday, -12.0, Currency::chf(), "Coffee Shop");
+        assert_eq!(rule_set.find_match(&shop).map(|(index, _)| index), Some(1));
+        assert_eq!(rule_set.apply(shop).transaction_type, TransactionType::Fun);
+
+        let expensive = transaction(monday, -25.0, Currency::chf(), "Coffee Shop");
+        assert_eq!(
+            rule_set.apply(expensive).transaction_type,
+            TransactionType::Unknown
+        );
+    }
+
+    #[test]
+    fn reject_invalid_rules() {
+        let rule = Rule {
+            name: "Broken".to_owned(),
+            note_regex: Some("(".to_owned()),
+            ..Default::default()
+        };
+        assert!(RuleSet::new(vec![rule]).is_err());
+
+        let rule = Rule {
+            name: "Unknown type".to_owned(),
+            set_type: Some("Spaceship".to_owned()),
+            ..Default::default()
+        };
+        assert!(RuleSet::new(vec![rule]).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::currency::Currency;
+use crate::transaction::transaction_type::TransactionType;
 use std::cmp::Ordering;
 use std::fmt;
+use std::num::ParseFloatError;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Amount {
@@ -26,6 +28,45 @@ impl Amount {
     }
 }
 
+/// Amount as entered by the user, expenses are written as positive numbers
+///
+/// A leading `+` marks income
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnteredAmount {
+    pub value: f64,
+    /// `None` if the amount was not marked
+    pub is_income: Option<bool>,
+}
+
+impl EnteredAmount {
+    /// Parse a number with `.` or `,` as decimal separator
+    pub fn parse(input: &str) -> Result<Self, ParseFloatError> {
+        let input = input.trim();
+        let (number, is_income) = match input.strip_prefix('+') {
+            Some(number) => (number, Some(true)),
+            None => (input, None),
+        };
+
+        let value = number.trim().replace(',', ".").parse::<f64>()?;
+
+        Ok(Self { value, is_income })
+    }
+
+    /// Return the stored value: negative for expenses, positive for income
+    ///
+    /// Unmarked amounts are income only if the type is income by default
+    pub fn signed_value(&self, transaction_type: TransactionType) -> f64 {
+        if self
+            .is_income
+            .unwrap_or_else(|| transaction_type.is_income_by_default())
+        {
+            self.value
+        } else {
+            -self.value
+        }
+    }
+}
+
 impl fmt::Display for Amount {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} {:.2}", self.currency, self.value)
@@ -37,3 +78,29 @@ impl PartialOrd for Amount {
         self.value.partial_cmp(&other.value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_entered_amount() {
+        let expense = EnteredAmount::parse("12,50").unwrap();
+        assert_eq!(expense.is_income, None);
+        assert_eq!(expense.signed_value(TransactionType::Eat), -12.5);
+
+        let income = EnteredAmount::parse(" +3500.00").unwrap();
+        assert_eq!(income.is_income, Some(true));
+        assert_eq!(income.signed_value(TransactionType::Unknown), 3500.0);
+
+        let salary = EnteredAmount::parse("3500").unwrap();
+        assert_eq!(salary.signed_value(TransactionType::Salary), 3500.0);
+
+        // A negative expense (e.g. a refund) stays positive, as before
+        let refund = EnteredAmount::parse("-20").unwrap();
+        assert_eq!(refund.signed_value(TransactionType::Eat), 20.0);
+
+        assert!(EnteredAmount::parse("+").is_err());
+        assert!(EnteredAmount::parse("abc").is_err());
+    }
+}
@@ -5,4 +5,12 @@ use super::amount::Amount;
 pub trait MainTransactionData {
     fn amount(&self) -> &Amount;
     fn date(&self) -> NaiveDate;
+
+    fn base_amount_ref(&self) -> Option<&Amount> {
+        None
+    }
+
+    fn note_ref(&self) -> Option<&str> {
+        None
+    }
 }
@@ -15,11 +15,16 @@ pub mod transaction_type;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Transaction {
+    /// Row ID, only set for transactions loaded from the database
+    pub uid: Option<i64>,
     pub date: NaiveDate,
     pub amount: Amount,
     pub base_amount: Option<Amount>,
     pub transaction_type: TransactionType,
     pub note: Option<String>,
+    /// Reference assigned by the source of an import (e.g. the bank's entry reference)
+    pub external_id: Option<String>,
+    pub tags: Vec<String>,
 }
 
 impl Transaction {
@@ -31,11 +36,14 @@ impl Transaction {
         note: Option<String>,
     ) -> Self {
         Transaction {
+            uid: None,
             date,
             amount,
             base_amount,
             transaction_type,
             note,
+            external_id: None,
+            tags: vec![],
         }
     }
 
@@ -63,6 +71,10 @@ impl Transaction {
         self.note.clone()
     }
 
+    pub fn external_id(&self) -> Option<String> {
+        self.external_id.clone()
+    }
+
     pub fn with_base_amount(&self, base_amount: Amount) -> Transaction {
         let mut clone = self.clone();
 
@@ -70,6 +82,14 @@ impl Transaction {
 
         clone
     }
+
+    pub fn with_external_id<S: Into<String>>(&self, external_id: S) -> Transaction {
+        let mut clone = self.clone();
+
+        clone.external_id = Some(external_id.into());
+
+        clone
+    }
 }
 
 impl fmt::Display for Transaction {
@@ -119,6 +139,14 @@ impl MainTransactionData for Transaction {
     fn date(&self) -> NaiveDate {
         self.date
     }
+
+    fn base_amount_ref(&self) -> Option<&Amount> {
+        self.base_amount.as_ref()
+    }
+
+    fn note_ref(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
 }
 
 impl MainTransactionData for &Transaction {
@@ -129,6 +157,14 @@ impl MainTransactionData for &Transaction {
     fn date(&self) -> NaiveDate {
         self.date
     }
+
+    fn base_amount_ref(&self) -> Option<&Amount> {
+        self.base_amount.as_ref()
+    }
+
+    fn note_ref(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
 }
 
 pub fn contains_transaction_in_currency(transactions: &[Transaction], currency: &Currency) -> bool {
@@ -141,13 +177,31 @@ impl FromRow<'_, SqliteRow> for Transaction {
     fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
         let currency: Currency = row.try_get("currency")?;
         let amount = Amount::new(row.try_get("amount")?, currency);
+        let base_amount: Option<f64> = row.try_get("base_amount")?;
+        let tags: String = row.try_get("tags")?;
 
         Ok(Self {
+            uid: row.try_get("uid")?,
             date: row.try_get("date")?,
             amount,
-            base_amount: None,
+            base_amount: base_amount.map(|value| Amount::new(value, Currency::base())),
             transaction_type: row.try_get("type")?,
             note: row.try_get("note")?,
+            external_id: row.try_get("external_id")?,
+            tags: split_tags(&tags),
         })
     }
 }
+
+/// Tags are stored as a comma separated list
+pub fn join_tags(tags: &[String]) -> String {
+    tags.join(",")
+}
+
+pub fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
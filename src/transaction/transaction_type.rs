@@ -2,7 +2,7 @@ use crate::error::Error;
 use std::str::FromStr;
 use std::{char, fmt};
 
-pub const NUMBER_OF_TYPES: usize = 15;
+pub const NUMBER_OF_TYPES: usize = 16;
 
 #[derive(Clone, PartialOrd, PartialEq, Eq, Copy, Debug, Hash, clap::ValueEnum, sqlx::Type)]
 #[repr(u8)]
@@ -35,6 +35,8 @@ pub enum TransactionType {
     Tax = b'X',
     // "J" => Banking
     Banking = b'J',
+    // "L" => Salary / Lohn
+    Salary = b'L',
 
     // Unknown
     Unknown = b'U',
@@ -46,7 +48,7 @@ impl TransactionType {
             "A" | "CAR" | "AUTO" => TransactionType::Car,
             "B" | "BODY" | "COSMETICS" => TransactionType::Body,
             "C" | "K" | "CLOTHES" | "KLEIDUNG" => TransactionType::Clothes,
-            "E" | "EAT" | "ESSEN" => TransactionType::Eat,
+            "E" | "EAT" | "FOOD" | "ESSEN" => TransactionType::Eat,
             "N" | "EDUCATION" | "AUSBILDUNG" => TransactionType::Education,
             "F" | "FUN" | "HOBBY" => TransactionType::Fun,
             "T" | "GAS" | "TANKEN" => TransactionType::Gas,
@@ -59,6 +61,7 @@ impl TransactionType {
             "X" | "TAX" | "TAXES" | "STEUERN" | "STEUER" => TransactionType::Tax,
             "H" | "HOUSE" | "HOME" => TransactionType::Home,
             "J" | "BANK" | "BANKING" => TransactionType::Banking,
+            "L" | "SALARY" | "WAGES" | "LOHN" | "GEHALT" => TransactionType::Salary,
             _ => TransactionType::Unknown,
         }
     }
@@ -84,6 +87,7 @@ impl TransactionType {
             TransactionType::Donation,
             TransactionType::Tax,
             TransactionType::Banking,
+            TransactionType::Salary,
         ]
     }
 
@@ -104,10 +108,38 @@ impl TransactionType {
             TransactionType::Donation,
             TransactionType::Tax,
             TransactionType::Banking,
+            TransactionType::Salary,
             TransactionType::Unknown,
         ]
     }
 
+    /// Whether unmarked amounts of this type are income
+    pub fn is_income_by_default(self) -> bool {
+        self == TransactionType::Salary
+    }
+
+    /// Return a single-word English name that `from_str()` maps back to the type
+    pub fn name(self) -> &'static str {
+        match self {
+            TransactionType::Body => "Body",
+            TransactionType::Car => "Car",
+            TransactionType::Clothes => "Clothes",
+            TransactionType::Eat => "Food",
+            TransactionType::Education => "Education",
+            TransactionType::Fun => "Fun",
+            TransactionType::Gas => "Gas",
+            TransactionType::Health => "Health",
+            TransactionType::Home => "Home",
+            TransactionType::Telecommunication => "Telecommunication",
+            TransactionType::Insurance => "Insurance",
+            TransactionType::Donation => "Donation",
+            TransactionType::Tax => "Tax",
+            TransactionType::Banking => "Banking",
+            TransactionType::Salary => "Salary",
+            TransactionType::Unknown => "Unknown",
+        }
+    }
+
     pub fn to_str(self) -> &'static str {
         match self {
             TransactionType::Body => "Body / Cosmetics",
@@ -124,6 +156,7 @@ impl TransactionType {
             TransactionType::Donation => "Donation / Spende",
             TransactionType::Tax => "Tax / Steuer",
             TransactionType::Banking => "Bank / Banking",
+            TransactionType::Salary => "Salary / Lohn",
             TransactionType::Unknown => "Diverse",
         }
     }
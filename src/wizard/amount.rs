@@ -1,19 +1,14 @@
 use crate::error::Res;
+use crate::transaction::amount::EnteredAmount;
 use dialoguer::theme::Theme;
-use dialoguer::Input;
+use dialoguer::{Input, Select};
 
-pub fn read_amount(theme: &dyn Theme) -> Res<f64> {
+pub fn read_amount(theme: &dyn Theme) -> Res<EnteredAmount> {
     let raw_amount = Input::<String>::with_theme(theme)
-        .with_prompt("Amount")
+        .with_prompt("Amount (start with + for income)")
         .interact_text()?;
 
-    let raw_amount_normalized = if raw_amount.contains(',') {
-        raw_amount.replace(',', ".")
-    } else {
-        raw_amount
-    };
-
-    match raw_amount_normalized.parse::<f64>() {
+    match EnteredAmount::parse(&raw_amount) {
         Ok(c) => Ok(c),
         Err(_) => {
             println!("Please enter a valid amount");
@@ -21,3 +16,14 @@ pub fn read_amount(theme: &dyn Theme) -> Res<f64> {
         }
     }
 }
+
+/// Ask whether the amount is income, `default_income` preselects the answer
+pub fn read_is_income(theme: &dyn Theme, default_income: bool) -> Res<bool> {
+    let i = Select::with_theme(theme)
+        .with_prompt("Expense or income")
+        .default(usize::from(default_income))
+        .items(&["Expense", "Income"])
+        .interact()?;
+
+    Ok(i == 1)
+}
@@ -0,0 +1,117 @@
+use crate::duplicate_check::{ClusterAction, DuplicateAction, DuplicateCandidate};
+use crate::error::Res;
+use crate::transaction::Transaction;
+use dialoguer::theme::Theme;
+use dialoguer::{MultiSelect, Select};
+
+const ACTIONS: [(DuplicateAction, bool, &str); 8] = [
+    (
+        DuplicateAction::Skip,
+        false,
+        "Skip the incoming transaction",
+    ),
+    (DuplicateAction::Keep, false, "Keep both"),
+    (
+        DuplicateAction::Replace,
+        false,
+        "Replace the existing transaction",
+    ),
+    (
+        DuplicateAction::Merge,
+        false,
+        "Merge into the existing transaction",
+    ),
+    (DuplicateAction::Skip, true, "Skip all remaining duplicates"),
+    (DuplicateAction::Keep, true, "Keep all remaining duplicates"),
+    (
+        DuplicateAction::Replace,
+        true,
+        "Replace the best match of all remaining duplicates",
+    ),
+    (
+        DuplicateAction::Merge,
+        true,
+        "Merge all remaining duplicates into their best match",
+    ),
+];
+
+/// Return the selected action and whether it applies to all remaining duplicates
+pub fn read_duplicate_action(theme: &dyn Theme) -> Res<(DuplicateAction, bool)> {
+    let labels: Vec<&str> = ACTIONS.iter().map(|(_, _, label)| *label).collect();
+    let i = Select::with_theme(theme)
+        .with_prompt("What should happen with the incoming transaction?")
+        .default(0)
+        .items(&labels)
+        .interact()?;
+
+    Ok((ACTIONS[i].0, ACTIONS[i].1))
+}
+
+/// Select the existing transaction to replace or merge into
+pub fn read_duplicate_target(theme: &dyn Theme, candidates: &[DuplicateCandidate]) -> Res<usize> {
+    if candidates.len() < 2 {
+        return Ok(0);
+    }
+
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|candidate| format!("{} ({})", candidate.transaction, candidate.describe()))
+        .collect();
+
+    Ok(Select::with_theme(theme)
+        .with_prompt("Existing transaction")
+        .default(0)
+        .items(&labels)
+        .interact()?)
+}
+
+pub fn read_cluster_action(theme: &dyn Theme) -> Res<ClusterAction> {
+    let actions = [
+        (ClusterAction::Keep, "Keep all"),
+        (ClusterAction::Merge, "Merge into one transaction"),
+        (ClusterAction::Delete, "Delete some of them"),
+        (ClusterAction::Stop, "Stop"),
+    ];
+    let labels: Vec<&str> = actions.iter().map(|(_, label)| *label).collect();
+    let i = Select::with_theme(theme)
+        .with_prompt("What should happen with these transactions?")
+        .default(0)
+        .items(&labels)
+        .interact()?;
+
+    Ok(actions[i].0)
+}
+
+/// Select the transaction that the others are merged into
+pub fn read_transaction_to_keep(theme: &dyn Theme, transactions: &[&Transaction]) -> Res<usize> {
+    Ok(Select::with_theme(theme)
+        .with_prompt("Transaction to keep")
+        .default(0)
+        .items(&labels(transactions))
+        .interact()?)
+}
+
+pub fn read_transactions_to_delete(
+    theme: &dyn Theme,
+    transactions: &[&Transaction],
+) -> Res<Vec<usize>> {
+    Ok(MultiSelect::with_theme(theme)
+        .with_prompt("Transactions to delete (space to select)")
+        .items(&labels(transactions))
+        .interact()?)
+}
+
+fn labels(transactions: &[&Transaction]) -> Vec<String> {
+    transactions
+        .iter()
+        .map(|transaction| {
+            format!(
+                "{} {} [{}] {}",
+                transaction.date,
+                transaction.amount,
+                transaction.transaction_type.identifier(),
+                transaction.note.as_deref().unwrap_or_default()
+            )
+        })
+        .collect()
+}
@@ -1,21 +1,29 @@
 mod amount;
 mod currency;
 mod date;
+mod duplicate;
 mod note;
 mod transaction_type;
 
-use self::amount::read_amount;
+use self::amount::{read_amount, read_is_income};
 use self::currency::read_currency;
 use self::date::read_date;
+use self::duplicate::{
+    read_cluster_action, read_duplicate_action, read_duplicate_target, read_transaction_to_keep,
+    read_transactions_to_delete,
+};
 use self::note::NoteWizard;
 use self::transaction_type::read_transaction_type;
 use self::transaction_type::read_transaction_type_or_skip;
+use crate::classifier::{Classifier, Suggestion};
 use crate::currency::Currency;
-use crate::duplicate_check::DuplicateChecker;
+use crate::duplicate_check::{
+    ClusterAction, DuplicateAction, DuplicateCandidate, DuplicateChecker,
+};
 use crate::error::Res;
 use crate::persistence::TransactionRepository;
 use crate::printer::PrinterTrait;
-use crate::transaction::amount::Amount;
+use crate::transaction::amount::{Amount, EnteredAmount};
 use crate::transaction::transaction_type::TransactionType;
 use crate::transaction::Transaction;
 use chrono::NaiveDate;
@@ -60,19 +68,21 @@ impl Wizard {
         printer.println("Answer the following questions to insert a new invoice");
         printer.println("(Press ctrl+c to exit)");
 
+        let classifier = Classifier::train(transactions);
         loop {
-            let transaction = self.create_transaction(transactions)?;
+            let transaction = self.create_transaction(transactions, &classifier)?;
 
             printer.print_newline();
             printer.print_subheader("Read the following transaction:");
             printer.print_transaction(base_currency, &transaction);
 
             let possible_duplicates =
-                DuplicateChecker::get_possible_duplicates(&transaction, transactions);
+                DuplicateChecker::default().find_candidates(&transaction, transactions);
             if !possible_duplicates.is_empty() {
                 printer.print_warning("⚠︎ Found possible duplicates:");
                 for possible_duplicate in possible_duplicates {
-                    printer.print_transaction(base_currency, possible_duplicate);
+                    printer.print_transaction(base_currency, possible_duplicate.transaction);
+                    printer.println(format!("  {}", possible_duplicate.describe()));
                 }
             }
 
@@ -110,37 +120,92 @@ impl Wizard {
         read_currency(self.theme.as_ref())
     }
 
-    pub fn read_amount(&self) -> Res<f64> {
+    pub fn read_amount(&self) -> Res<EnteredAmount> {
         read_amount(self.theme.as_ref())
     }
 
-    pub fn read_transaction_type(&self, allow_unknown: bool) -> Res<TransactionType> {
-        read_transaction_type(self.theme.as_ref(), allow_unknown)
+    pub fn read_is_income(&self, default_income: bool) -> Res<bool> {
+        read_is_income(self.theme.as_ref(), default_income)
+    }
+
+    pub fn read_transaction_type(
+        &self,
+        allow_unknown: bool,
+        suggestion: Option<Suggestion>,
+    ) -> Res<TransactionType> {
+        read_transaction_type(self.theme.as_ref(), allow_unknown, suggestion)
     }
 
     pub fn read_transaction_type_or_skip(
         &self,
         allow_unknown: bool,
+        suggestion: Option<Suggestion>,
     ) -> Res<Option<TransactionType>> {
-        read_transaction_type_or_skip(self.theme.as_ref(), allow_unknown)
+        read_transaction_type_or_skip(self.theme.as_ref(), allow_unknown, suggestion)
+    }
+
+    /// Return the action for a possible duplicate and whether it applies to all remaining ones
+    pub fn read_duplicate_action(&self) -> Res<(DuplicateAction, bool)> {
+        read_duplicate_action(self.theme.as_ref())
     }
 
-    fn create_transaction(&self, transactions: &[Transaction]) -> Res<Transaction> {
+    pub fn read_duplicate_target(&self, candidates: &[DuplicateCandidate]) -> Res<usize> {
+        read_duplicate_target(self.theme.as_ref(), candidates)
+    }
+
+    pub fn read_cluster_action(&self) -> Res<ClusterAction> {
+        read_cluster_action(self.theme.as_ref())
+    }
+
+    pub fn read_transaction_to_keep(&self, transactions: &[&Transaction]) -> Res<usize> {
+        read_transaction_to_keep(self.theme.as_ref(), transactions)
+    }
+
+    pub fn read_transactions_to_delete(&self, transactions: &[&Transaction]) -> Res<Vec<usize>> {
+        read_transactions_to_delete(self.theme.as_ref(), transactions)
+    }
+
+    fn create_transaction(
+        &self,
+        transactions: &[Transaction],
+        classifier: &Classifier,
+    ) -> Res<Transaction> {
         let theme = self.theme.as_ref();
         let date = self.read_date()?;
         let currency = self.read_currency()?;
 
-        // Multiply be -1.0 to treat the input as expense
-        let amount = -1.0 * self.read_amount()?;
-        let transaction_type = self.read_transaction_type(false)?;
+        let entered_amount = self.read_amount()?;
+        // The note is read before the type, so it can be used for the suggestion
         let note = self.note_wizard.read(theme, transactions)?;
-
-        Ok(Transaction::new(
+        let transaction = Transaction::new(
             date,
-            Amount::new(amount, currency),
+            Amount::new(
+                entered_amount.signed_value(TransactionType::Unknown),
+                currency.clone(),
+            ),
             None,
-            transaction_type,
+            TransactionType::Unknown,
             Some(note),
-        ))
+        );
+
+        let transaction_type =
+            self.read_transaction_type(false, classifier.suggest(&transaction))?;
+
+        // Unmarked amounts are expenses unless the type is income by default
+        let is_income = self.read_is_income(
+            entered_amount
+                .is_income
+                .unwrap_or_else(|| transaction_type.is_income_by_default()),
+        )?;
+        let entered_amount = EnteredAmount {
+            is_income: Some(is_income),
+            ..entered_amount
+        };
+
+        Ok(Transaction {
+            amount: Amount::new(entered_amount.signed_value(transaction_type), currency),
+            transaction_type,
+            ..transaction
+        })
     }
 }
@@ -1,17 +1,18 @@
+use crate::classifier::Suggestion;
 use crate::error::Res;
 use crate::transaction::transaction_type::TransactionType;
 use dialoguer::theme::Theme;
 use dialoguer::FuzzySelect;
 
-pub fn read_transaction_type(theme: &dyn Theme, allow_unknown: bool) -> Res<TransactionType> {
-    let all = if allow_unknown {
-        TransactionType::all().to_vec()
-    } else {
-        TransactionType::all_known().to_vec()
-    };
+pub fn read_transaction_type(
+    theme: &dyn Theme,
+    allow_unknown: bool,
+    suggestion: Option<Suggestion>,
+) -> Res<TransactionType> {
+    let all = selectable_types(allow_unknown);
     let i = FuzzySelect::with_theme(theme)
-        .with_prompt("Type")
-        .default(0)
+        .with_prompt(with_suggestion("Type", suggestion))
+        .default(default_index(&all, suggestion))
         .items(&all[..])
         .interact()?;
 
@@ -21,15 +22,15 @@ pub fn read_transaction_type(theme: &dyn Theme, allow_unknown: bool) -> Res<Tran
 pub fn read_transaction_type_or_skip(
     theme: &dyn Theme,
     allow_unknown: bool,
+    suggestion: Option<Suggestion>,
 ) -> Res<Option<TransactionType>> {
-    let all = if allow_unknown {
-        TransactionType::all().to_vec()
-    } else {
-        TransactionType::all_known().to_vec()
-    };
+    let all = selectable_types(allow_unknown);
     let selection = FuzzySelect::with_theme(theme)
-        .with_prompt("Select type (or press ESC to ignore this transaction)")
-        .default(0)
+        .with_prompt(with_suggestion(
+            "Select type (or press ESC to ignore this transaction)",
+            suggestion,
+        ))
+        .default(default_index(&all, suggestion))
         .items(&all[..])
         .interact_opt()?;
 
@@ -38,3 +39,30 @@ pub fn read_transaction_type_or_skip(
         None => Ok(None),
     }
 }
+
+fn selectable_types(allow_unknown: bool) -> Vec<TransactionType> {
+    if allow_unknown {
+        TransactionType::all().to_vec()
+    } else {
+        TransactionType::all_known().to_vec()
+    }
+}
+
+/// Pre-select the suggested type
+fn default_index(all: &[TransactionType], suggestion: Option<Suggestion>) -> usize {
+    suggestion
+        .and_then(|s| all.iter().position(|t| *t == s.transaction_type))
+        .unwrap_or(0)
+}
+
+fn with_suggestion(prompt: &str, suggestion: Option<Suggestion>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(
+            "{} (suggested: {}, {:.0}% confidence)",
+            prompt,
+            suggestion.transaction_type,
+            suggestion.confidence * 100.0
+        ),
+        None => prompt.to_owned(),
+    }
+}